@@ -1,28 +1,126 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
+use serde::{Deserialize, Deserializer};
 use serde_derive::{Deserialize, Serialize};
 
+/// Versions can be exact, or constraints like `>=17, <22`, `3.9.*` or `~8.5`, see [crate::utils::version::VersionConstraint].
+/// A section given just by its version, like `maven = "3.9.*"`, is the same as the `version` in that section.
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct JavaboxConfig {
+    #[serde(default, deserialize_with = "version_or_section")]
     pub java: Option<JavaConfig>,
+    #[serde(default, deserialize_with = "version_or_section")]
     pub maven: Option<MavenConfig>,
+    #[serde(default, deserialize_with = "version_or_section")]
     pub gradle: Option<GradleConfig>,
+    /// environment variables for all tools; values may reference others, see [env::ProjectEnv]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// where the configuration comes from, like the path of `javabox.toml`
+    #[serde(skip)]
+    pub origin: String,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct JavaConfig {
     pub version: String,
+    /// JDK distribution, like `Temurin` or `OracleJDK`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct MavenConfig {
     pub version: String,
+    /// the default location is used when empty
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub download_url: String,
+    /// pinned checksum of the distribution, like `distributionSha256Sum` in `maven-wrapper.properties`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution_sha256_sum: Option<String>,
+    /// `distributionType` of maven-wrapper: `bin`, `script`, `source` or `only-script`; it decides where distributions are installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution_type: Option<String>,
+    /// `wrapperVersion` of maven-wrapper
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrapper_version: Option<String>,
+    /// arguments added to those of `.mvn/maven.config`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maven_config: Vec<String>,
+    /// JVM options added to those of `.mvn/jvm.config` and `MAVEN_OPTS`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jvm_config: Vec<String>,
+    /// started in a module, run from the reactor root as `-pl <module> -am`, building also the modules it depends on
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub build_from_root: bool,
+    #[serde(default, skip_serializing_if = "ToolOptions::is_empty")]
+    pub opts: ToolOptions,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct GradleConfig {
+    /// the wrapper's version is used when empty
+    #[serde(default)]
     pub version: String,
+    #[serde(default, skip_serializing_if = "ToolOptions::is_empty")]
+    pub opts: ToolOptions,
+}
+
+/// Launch options of a tool, like `[maven.opts]`:
+/// ```toml
+/// [maven.opts]
+/// MAVEN_OPTS = "$MAVEN_OPTS -Xmx2g"
+/// JAVA_TOOL_OPTIONS = "-Dfile.encoding=UTF-8"
+/// properties = { "maven.repo.local" = "${PROJECT_DIR}/.m2/repository" }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolOptions {
+    /// system properties, passed as `-Dname=value` arguments
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+    /// environment variables, over those of `[env]`
+    #[serde(flatten)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl ToolOptions {
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty() && self.env.is_empty()
+    }
+}
+
+impl From<String> for JavaConfig {
+    fn from(version: String) -> Self {
+        JavaConfig { version, vendor: None }
+    }
+}
+
+impl From<String> for MavenConfig {
+    fn from(version: String) -> Self {
+        MavenConfig { version, ..Default::default() }
+    }
+}
+
+impl From<String> for GradleConfig {
+    fn from(version: String) -> Self {
+        GradleConfig { version, ..Default::default() }
+    }
+}
+
+/// Accepts both `maven = "3.9.*"` and the `[maven]` table
+fn version_or_section<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where D: Deserializer<'de>, T: Deserialize<'de> + From<String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Section<T> {
+        Version(String),
+        Table(T),
+    }
+    Ok(Option::<Section<T>>::deserialize(deserializer)?.map(|section| match section {
+        Section::Version(version) => T::from(version),
+        Section::Table(section) => section,
+    }))
 }
 
 const CONFIG_NAME: &'static str = "javabox.toml";
@@ -32,10 +130,21 @@ impl JavaboxConfig {
         dir.join(CONFIG_NAME).is_file()
     }
 
+    /// Loads `javabox.toml` from given directory; without it, the configuration is inferred by given function.
+    /// Inferred configuration is cached until the project files change.
+    pub fn load_or_infer(dir: &Path, tool: &str, infer: impl FnOnce(&Path) -> anyhow::Result<Self>) -> anyhow::Result<Self> {
+        if Self::is_inside(dir) {
+            Self::load(dir)
+        } else {
+            cache::load_or_infer(dir, tool, infer)
+        }
+    }
+
     pub fn load(dir: &Path) -> anyhow::Result<Self> {
         let config_file = dir.join(CONFIG_NAME);
         log::trace!("JavaboxConfig::load({})", config_file.display());
-        let config: JavaboxConfig = confy::load_path(config_file)?;
+        let mut config: JavaboxConfig = confy::load_path(&config_file)?;
+        config.origin = config_file.display().to_string();
         Ok(config)
     }
 
@@ -51,3 +160,57 @@ impl JavaboxConfig {
         Ok(())
     }
 }
+
+pub mod cache;
+pub mod env;
+pub mod global;
+pub mod lock;
+pub mod overrides;
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::testing::TempDir;
+
+    use super::JavaboxConfig;
+
+    #[test]
+    fn test_version_shorthand() {
+        let tmp = TempDir::new("config");
+        std::fs::write(tmp.join("javabox.toml"), "java = \">=17, <22\"\nmaven = \"3.9.*\"\n\n[gradle]\nversion = \"~8.5\"\n").unwrap();
+        let config = JavaboxConfig::load(&tmp).unwrap();
+        assert_eq!(">=17, <22", config.java.unwrap().version);
+        let maven = config.maven.unwrap();
+        assert_eq!("3.9.*", maven.version);
+        assert_eq!("", maven.download_url);
+        assert_eq!("~8.5", config.gradle.unwrap().version);
+    }
+
+    #[test]
+    fn test_env_and_opts() {
+        let tmp = TempDir::new("config-env");
+        std::fs::write(tmp.join("javabox.toml"), r#"java = "17"
+
+[env]
+DATA_DIR = "${PROJECT_DIR}/data"
+
+[maven]
+version = "3.9.6"
+
+[maven.opts]
+MAVEN_OPTS = "$MAVEN_OPTS -Xmx2g"
+properties = { "skipITs" = "true" }
+
+[gradle.opts]
+GRADLE_OPTS = "-Xmx1g"
+"#).unwrap();
+        let config = JavaboxConfig::load(&tmp).unwrap();
+        assert_eq!("${PROJECT_DIR}/data", config.env["DATA_DIR"]);
+        let maven = config.maven.unwrap();
+        assert_eq!("3.9.6", maven.version);
+        assert_eq!("$MAVEN_OPTS -Xmx2g", maven.opts.env["MAVEN_OPTS"]);
+        assert_eq!("true", maven.opts.properties["skipITs"]);
+        let gradle = config.gradle.unwrap();
+        assert_eq!("", gradle.version);
+        assert_eq!("-Xmx1g", gradle.opts.env["GRADLE_OPTS"]);
+    }
+}
@@ -0,0 +1,162 @@
+//! # Resolution cache
+//!
+//! Inferring the configuration of a project without `javabox.toml` reads build files, and may need the network
+//! (like for the latest Maven release). The result is therefore kept in `~/.javabox/projects`,
+//! and reused for as long as the files it was inferred from stay unchanged: the well-known build files of the project,
+//! and any other file read while inferring, like parent POMs; readers report those by [note_input].
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use dir::home_dir;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::JavaboxConfig;
+use crate::utils;
+
+const CACHE_DIR: &str = ".javabox/projects";
+
+/// Files in the project directory that the configuration is inferred from
+const INPUT_FILES: [&str; 8] = [
+    "pom.xml",
+    ".mvn/wrapper/maven-wrapper.properties",
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
+    "gradle/wrapper/gradle-wrapper.properties",
+    "jdk.version",
+];
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    project_dir: PathBuf,
+    fingerprint: String,
+    /// files read while inferring, besides [INPUT_FILES]
+    #[serde(default)]
+    inputs: Vec<PathBuf>,
+    config: JavaboxConfig,
+}
+
+thread_local! {
+    /// files read by the inference in progress, if any
+    static INPUTS: RefCell<Option<Vec<PathBuf>>> = const { RefCell::new(None) };
+}
+
+/// Notes that the configuration being inferred depends on given file.
+pub fn note_input(path: &Path) {
+    INPUTS.with(|inputs| {
+        if let Some(inputs) = inputs.borrow_mut().as_mut() {
+            if !inputs.iter().any(|input| input == path) {
+                inputs.push(path.to_path_buf());
+            }
+        }
+    });
+}
+
+/// Runs the inference, and collects the files it reads.
+fn recording_inputs<T>(infer: impl FnOnce() -> T) -> (T, Vec<PathBuf>) {
+    INPUTS.with(|inputs| inputs.replace(Some(Vec::new())));
+    let result = infer();
+    let inputs = INPUTS.with(|inputs| inputs.take()).unwrap_or_default();
+    (result, inputs)
+}
+
+/// Returns the cached configuration of the project, or infers it and caches the result.
+/// The `tool` distinguishes entries of projects that can be built by more tools.
+pub fn load_or_infer(project_dir: &Path, tool: &str, infer: impl FnOnce(&Path) -> anyhow::Result<JavaboxConfig>) -> anyhow::Result<JavaboxConfig> {
+    let project_dir = project_dir.canonicalize()?;
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    let cache_file = cache_file(&user_home, &project_dir, tool);
+    if cache_file.is_file() {
+        match confy::load_path::<CacheEntry>(&cache_file) {
+            Ok(mut entry) if entry.fingerprint == fingerprint(&project_dir, &entry.inputs) => {
+                log::debug!("Using cached configuration {}", cache_file.display());
+                entry.config.origin = inferred_origin(&project_dir);
+                return Ok(entry.config);
+            }
+            Ok(_) => log::debug!("Project files changed since {} was cached", cache_file.display()),
+            Err(e) => log::warn!("Ignoring unreadable cache {} :: {e}", cache_file.display()),
+        }
+    }
+    let (config, inputs) = recording_inputs(|| infer(&project_dir));
+    let mut config = config?;
+    config.origin = inferred_origin(&project_dir);
+    let fingerprint = fingerprint(&project_dir, &inputs);
+    let entry = CacheEntry { project_dir, fingerprint, inputs, config };
+    // the cache is only an optimization
+    if let Err(e) = confy::store_path(&cache_file, &entry) {
+        log::warn!("Failed to cache configuration in {} :: {e}", cache_file.display());
+    }
+    Ok(entry.config)
+}
+
+fn inferred_origin(project_dir: &Path) -> String {
+    format!("inferred from project files in {}", project_dir.display())
+}
+
+fn cache_file(user_home: &Path, project_dir: &Path, tool: &str) -> PathBuf {
+    let name = project_dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let hash = utils::md5decimal(&project_dir.display().to_string());
+    user_home.join(CACHE_DIR).join(format!("{name}-{hash}-{tool}.toml"))
+}
+
+/// Sizes and modification times of the input files; new javabox version invalidates everything too
+fn fingerprint(project_dir: &Path, inputs: &[PathBuf]) -> String {
+    let mut fingerprint = format!("javabox-{}", env!("CARGO_PKG_VERSION"));
+    let files = INPUT_FILES.iter().map(|name| (name.to_string(), project_dir.join(name)))
+        .chain(inputs.iter().map(|input| (input.display().to_string(), input.clone())));
+    for (name, path) in files {
+        if let Ok(stat) = std::fs::metadata(path) {
+            let mtime = stat.modified().ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|time| time.as_nanos())
+                .unwrap_or_default();
+            fingerprint.push_str(&format!(";{name}:{}:{mtime}", stat.len()));
+        }
+    }
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::utils::testing::TempDir;
+
+    use super::{fingerprint, note_input, recording_inputs};
+
+    #[test]
+    fn test_fingerprint() {
+        let tmp = TempDir::new("cache");
+        let empty = fingerprint(&tmp, &[]);
+        std::fs::write(tmp.join("pom.xml"), "<project/>").unwrap();
+        let with_pom = fingerprint(&tmp, &[]);
+        assert_ne!(empty, with_pom);
+        assert_eq!(with_pom, fingerprint(&tmp, &[]));
+        std::fs::File::options().write(true).open(tmp.join("pom.xml")).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert_ne!(with_pom, fingerprint(&tmp, &[]));
+    }
+
+    #[test]
+    fn test_inputs_outside_project() {
+        let tmp = TempDir::new("cache-inputs");
+        std::fs::create_dir_all(tmp.join("app")).unwrap();
+        let parent_pom = tmp.join("pom.xml");
+        std::fs::write(&parent_pom, "<project/>").unwrap();
+        let ((), inputs) = recording_inputs(|| {
+            note_input(&parent_pom);
+            note_input(&parent_pom);
+        });
+        assert_eq!(vec![parent_pom.clone()], inputs);
+        // nothing is recorded outside of an inference
+        note_input(&tmp);
+        let project_dir = tmp.join("app");
+        let before = fingerprint(&project_dir, &inputs);
+        std::fs::write(&parent_pom, "<project><version>2</version></project>").unwrap();
+        assert_ne!(before, fingerprint(&project_dir, &inputs));
+    }
+}
@@ -0,0 +1,174 @@
+//! # Project environment
+//!
+//! `javabox.toml` sets environment variables for the tools, in place of ad-hoc `.envrc` files:
+//! ```toml
+//! [env]
+//! JAVA_TOOL_OPTIONS = "-Dfile.encoding=UTF-8"
+//! DATA_DIR = "${PROJECT_DIR}/data"
+//!
+//! [maven.opts]
+//! MAVEN_OPTS = "$MAVEN_OPTS -Xmx2g"
+//! properties = { "test.data" = "$DATA_DIR" }
+//!
+//! [gradle.opts]
+//! GRADLE_OPTS = "-Xmx1g"
+//! ```
+//! Values may reference variables as `$NAME` or `${NAME}`, and `$$` stands for `$`. A reference finds the variable
+//! in the same section first, then in `[env]`, then in the environment of javabox; `PROJECT_DIR` is the project root.
+//! A variable referencing itself gets its previous value, so that `MAVEN_OPTS = "$MAVEN_OPTS -Xmx2g"` appends.
+//! Undefined variables expand to nothing, like in shells.
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::bail;
+
+use crate::config::{JavaboxConfig, ToolOptions};
+
+/// Refers to the project root in values
+pub const PROJECT_DIR: &str = "PROJECT_DIR";
+
+/// What a tool gets from the project configuration, with references expanded
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProjectEnv {
+    /// variables to set
+    pub vars: BTreeMap<String, String>,
+    /// system properties, as `-Dname=value` arguments
+    pub properties: Vec<String>,
+}
+
+impl ProjectEnv {
+    /// Expands `[env]` and the tool's options, if any, for the project in given directory.
+    pub fn resolve(config: &JavaboxConfig, opts: Option<&ToolOptions>, project_dir: &Path) -> anyhow::Result<Self> {
+        let project_dir = project_dir.display().to_string();
+        let outer = |name: &str| match name {
+            PROJECT_DIR => Some(project_dir.clone()),
+            _ => std::env::var(name).ok(),
+        };
+        let mut vars = expand_section(&config.env, &outer)?;
+        let mut properties = Vec::new();
+        if let Some(opts) = opts {
+            let outer = |name: &str| vars.get(name).cloned().or_else(|| outer(name));
+            let tool_vars = expand_section(&opts.env, &outer)?;
+            let lookup = |name: &str| tool_vars.get(name).cloned().or_else(|| outer(name));
+            for (name, value) in &opts.properties {
+                properties.push(format!("-D{name}={}", expand(value, &mut |name| Ok(lookup(name)))?));
+            }
+            vars.extend(tool_vars);
+        }
+        for (name, value) in &vars {
+            log::debug!("{name}={value}");
+        }
+        Ok(ProjectEnv { vars, properties })
+    }
+
+    /// Value of the variable, as the tool sees it
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned().or_else(|| std::env::var(name).ok())
+    }
+
+    pub fn apply(&self, command: &mut Command) {
+        command.envs(&self.vars);
+    }
+}
+
+/// Expands all values of the section; references to other variables of the section are expanded first.
+fn expand_section(section: &BTreeMap<String, String>, outer: &dyn Fn(&str) -> Option<String>) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut expanded = BTreeMap::new();
+    for name in section.keys() {
+        expand_var(name, section, outer, &mut Vec::new(), &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+fn expand_var(name: &str, section: &BTreeMap<String, String>, outer: &dyn Fn(&str) -> Option<String>,
+              stack: &mut Vec<String>, expanded: &mut BTreeMap<String, String>) -> anyhow::Result<String> {
+    if let Some(value) = expanded.get(name) {
+        return Ok(value.clone());
+    }
+    if stack.iter().any(|pending| pending == name) {
+        bail!("Variable {name} references itself through {}", stack.join(" -> "));
+    }
+    stack.push(name.to_string());
+    let value = expand(&section[name], &mut |reference: &str| match section.contains_key(reference) && reference != name {
+        true => expand_var(reference, section, outer, stack, expanded).map(Some),
+        false => Ok(outer(reference)),
+    })?;
+    stack.pop();
+    expanded.insert(name.to_string(), value.clone());
+    Ok(value)
+}
+
+/// Replaces `$NAME` and `${NAME}` with what the lookup finds, and `$$` with `$`.
+fn expand(value: &str, lookup: &mut dyn FnMut(&str) -> anyhow::Result<Option<String>>) -> anyhow::Result<String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let (name, remainder) = if let Some(braced) = rest.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => bail!("Unclosed ${{ in '{value}'"),
+            }
+        } else if let Some(remainder) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = remainder;
+            continue;
+        } else {
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        if name.is_empty() {
+            // a lone `$` stays as it is
+            result.push('$');
+        } else {
+            result.push_str(&lookup(name)?.unwrap_or_default());
+        }
+        rest = remainder;
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use crate::config::{JavaboxConfig, ToolOptions};
+
+    use super::{expand, ProjectEnv};
+
+    fn map(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut lookup = |name: &str| Ok(Some(name).filter(|name| *name == "A").map(|_| "alpha".to_string()));
+        assert_eq!("alpha/alpha_x/$/-/$", expand("$A/${A}_x/$$/$UNDEFINED-/$", &mut lookup).unwrap());
+        assert!(expand("${A", &mut lookup).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let config = JavaboxConfig {
+            env: map(&[("DATA", "${PROJECT_DIR}/data"), ("URL", "file:$DATA"), ("JAVABOX_TEST_UNSET", "[$JAVABOX_TEST_UNSET]")]),
+            ..Default::default()
+        };
+        let opts = ToolOptions {
+            properties: map(&[("data.url", "$URL")]),
+            env: map(&[("JAVABOX_TEST_UNSET", "$JAVABOX_TEST_UNSET -Xmx2g"), ("URL", "$URL?cached")]),
+        };
+        let env = ProjectEnv::resolve(&config, Some(&opts), Path::new("/work/project")).unwrap();
+        assert_eq!(map(&[("DATA", "/work/project/data"), ("URL", "file:/work/project/data?cached"),
+            ("JAVABOX_TEST_UNSET", "[] -Xmx2g")]), env.vars);
+        assert_eq!(vec!["-Ddata.url=file:/work/project/data?cached"], env.properties);
+        let config = JavaboxConfig {
+            env: map(&[("A", "$B"), ("B", "${A}")]),
+            ..Default::default()
+        };
+        assert!(ProjectEnv::resolve(&config, None, Path::new("/work/project")).is_err());
+    }
+}
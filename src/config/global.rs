@@ -0,0 +1,326 @@
+//! # User configuration
+//!
+//! Global settings live in `~/.config/javabox/config.toml` (or under `$XDG_CONFIG_HOME`):
+//! ```toml
+//! update_check_interval = "24h"
+//! hash_check = "enforce"
+//!
+//! [java]
+//! vendor = "temurin"
+//! default_major = { temurin = "21", oracle = "17" }
+//! aliases = { lts = "21" }
+//!
+//! [maven]
+//! enforce = "3.9.6"
+//!
+//! [gradle]
+//! update_check_interval = "never"
+//! ```
+//!
+//! Effective values are layered, the first one found wins:
+//! 1. command-line overrides, like `@@jdk=21::`, see [crate::config::overrides]
+//! 2. environment, like `JAVABOX_JAVA_VERSION` or `JAVABOX_HASH_CHECK`
+//! 3. `enforce` of the tool in user configuration, meant for temporary use
+//! 4. the project: `jdk.version`, `javabox.toml`, or what was inferred from build files
+//! 5. defaults in user configuration: `default_major` per vendor (for java), `default`
+//! 6. built-in defaults
+//!
+//! Version aliases apply to the resulting value.
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use dir::home_dir;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::overrides;
+
+/// Used unless configured otherwise
+const DEFAULT_UPDATE_CHECK_INTERVAL: &str = "24h";
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    /// minimal interval between checks for new versions, like `24h`, `30m`, `7d` or `never`
+    update_check_interval: Option<String>,
+    hash_check: Option<HashCheck>,
+    #[serde(default)]
+    java: GlobalJavaConfig,
+    #[serde(default)]
+    maven: GlobalToolConfig,
+    #[serde(default)]
+    gradle: GlobalToolConfig,
+    /// where it was loaded from
+    #[serde(skip)]
+    file: String,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct GlobalJavaConfig {
+    vendor: Option<String>,
+    /// default major version per vendor, like `temurin = "21"`
+    #[serde(default)]
+    default_major: BTreeMap<String, String>,
+    #[serde(flatten)]
+    tool: GlobalToolConfig,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct GlobalToolConfig {
+    /// used when the project does not say
+    default: Option<String>,
+    /// overrides the project, meant for temporary use
+    enforce: Option<String>,
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    /// overrides the global `update_check_interval` for this tool
+    update_check_interval: Option<String>,
+}
+
+/// Verification of downloaded distributions
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashCheck {
+    /// never verify
+    Skip,
+    /// verify when a checksum is pinned or published
+    #[default]
+    Default,
+    /// refuse downloads that cannot be verified
+    Enforce,
+}
+
+/// A configuration value, and where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub value: String,
+    pub origin: String,
+}
+
+static GLOBAL: OnceLock<GlobalConfig> = OnceLock::new();
+
+impl Setting {
+    pub fn new(value: &str, origin: &str) -> Self {
+        Setting { value: value.to_string(), origin: origin.to_string() }
+    }
+
+    /// Value of a non-empty environment variable
+    fn from_env(var: &str) -> Option<Self> {
+        std::env::var(var).ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| Setting::new(value.trim(), var))
+    }
+}
+
+impl GlobalConfig {
+    /// The user configuration of this process, loaded on first use
+    pub fn get() -> anyhow::Result<&'static GlobalConfig> {
+        if let Some(config) = GLOBAL.get() {
+            return Ok(config);
+        }
+        let config = Self::load()?;
+        Ok(GLOBAL.get_or_init(|| config))
+    }
+
+    fn load() -> anyhow::Result<Self> {
+        Self::load_file(&config_file())
+    }
+
+    fn load_file(config_file: &Path) -> anyhow::Result<Self> {
+        if !config_file.is_file() {
+            log::trace!("No user configuration in {}", config_file.display());
+            return Ok(GlobalConfig::default());
+        }
+        log::trace!("GlobalConfig::load({})", config_file.display());
+        let mut config: GlobalConfig = confy::load_path(config_file)
+            .map_err(|e| anyhow::anyhow!("Invalid user configuration {} :: {e}", config_file.display()))?;
+        config.file = config_file.display().to_string();
+        Ok(config)
+    }
+
+    fn origin(&self, key: &str) -> String {
+        format!("{} [{key}]", self.file)
+    }
+
+    fn tool(&self, tool: &str) -> Option<&GlobalToolConfig> {
+        match tool {
+            "java" => Some(&self.java.tool),
+            "maven" => Some(&self.maven),
+            "gradle" => Some(&self.gradle),
+            _ => None,
+        }
+    }
+
+    /// Layers the version of a tool over the project's one; `default` is consulted just before the user's own default.
+    pub fn version(&self, tool: &str, project: Option<Setting>, default: Option<Setting>) -> Option<Setting> {
+        let config = self.tool(tool)?;
+        let setting = overrides::get(&format!("{tool}.version"))
+            .or(Setting::from_env(&format!("JAVABOX_{}_VERSION", tool.to_uppercase())))
+            .or(config.enforce.as_ref().map(|enforce| Setting::new(enforce, &self.origin(&format!("{tool}.enforce")))))
+            .or(project)
+            .or(default)
+            .or(config.default.as_ref().map(|version| Setting::new(version, &self.origin(&format!("{tool}.default")))))?;
+        match config.aliases.get(&setting.value) {
+            Some(version) => Some(Setting {
+                value: version.clone(),
+                origin: format!("{}, alias '{}' from {}", setting.origin, setting.value, self.origin(&format!("{tool}.aliases"))),
+            }),
+            None => Some(setting),
+        }
+    }
+
+    /// Layers the JDK vendor over the project's one
+    pub fn java_vendor(&self, project: Option<Setting>) -> Option<Setting> {
+        overrides::get("java.vendor")
+            .or(Setting::from_env("JAVABOX_JAVA_VENDOR"))
+            .or(project)
+            .or(self.java.vendor.as_ref().map(|vendor| Setting::new(vendor, &self.origin("java.vendor"))))
+    }
+
+    /// The user's default major version for the vendor, recognized by given function among the configured names
+    pub fn java_default_major(&self, is_vendor: impl Fn(&str) -> bool) -> Option<Setting> {
+        self.java.default_major.iter()
+            .find(|(vendor, _)| is_vendor(vendor))
+            .map(|(vendor, major)| Setting::new(major, &self.origin(&format!("java.default_major.{vendor}"))))
+    }
+
+    /// Minimal interval between checks for new versions of the tool; the tool's own settings win over global ones.
+    pub fn update_check_interval(&self, tool: &str) -> anyhow::Result<(Duration, Setting)> {
+        let key = format!("{tool}.update_check_interval");
+        let setting = Setting::from_env(&format!("JAVABOX_{}_UPDATE_CHECK_INTERVAL", tool.to_uppercase()))
+            .or(Setting::from_env("JAVABOX_UPDATE_CHECK_INTERVAL"))
+            .or(self.tool(tool).and_then(|config| config.update_check_interval.as_ref()).map(|interval| Setting::new(interval, &self.origin(&key))))
+            .or(self.update_check_interval.as_ref().map(|interval| Setting::new(interval, &self.origin("update_check_interval"))))
+            .unwrap_or(Setting::new(DEFAULT_UPDATE_CHECK_INTERVAL, "built-in default"));
+        let interval = parse_interval(&setting.value)
+            .ok_or(anyhow::anyhow!("Invalid update check interval '{}' from {}; use like `30m`, `24h`, `7d` or `never`", setting.value, setting.origin))?;
+        Ok((interval, setting))
+    }
+
+    pub fn hash_check(&self) -> anyhow::Result<(HashCheck, Setting)> {
+        if let Some(setting) = Setting::from_env("JAVABOX_HASH_CHECK") {
+            let hash_check = match setting.value.to_lowercase().as_str() {
+                "skip" => HashCheck::Skip,
+                "default" => HashCheck::Default,
+                "enforce" => HashCheck::Enforce,
+                _ => anyhow::bail!("Invalid JAVABOX_HASH_CHECK '{}'; use skip, default or enforce", setting.value),
+            };
+            return Ok((hash_check, setting));
+        }
+        Ok(match self.hash_check {
+            Some(hash_check) => (hash_check, Setting::new(&format!("{hash_check:?}").to_lowercase(), &self.origin("hash_check"))),
+            None => (HashCheck::Default, Setting::new("default", "built-in default")),
+        })
+    }
+}
+
+/// Minimal interval between checks for new versions of the tool, see [GlobalConfig::update_check_interval]
+pub fn update_check_interval(tool: &str) -> std::io::Result<Duration> {
+    GlobalConfig::get()
+        .and_then(|config| config.update_check_interval(tool))
+        .map(|(interval, _)| interval)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Verification of downloads, see [GlobalConfig::hash_check]
+pub fn hash_check() -> std::io::Result<(HashCheck, Setting)> {
+    GlobalConfig::get()
+        .and_then(GlobalConfig::hash_check)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// `$XDG_CONFIG_HOME/javabox/config.toml`, by default in `~/.config`
+pub fn config_file() -> PathBuf {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home_dir().expect("There is no HOME directory?!").join(".config"));
+    config_home.join("javabox/config.toml")
+}
+
+/// Parses intervals like `90s`, `30m`, `24h` or `7d`; plain number means seconds, and `never` is [Duration::MAX].
+fn parse_interval(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if text == "never" {
+        return Some(Duration::MAX);
+    }
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(n) => text.split_at(n),
+        None => (text, "s"),
+    };
+    let secs = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 24 * 3600,
+        _ => return None,
+    };
+    // too large a number is as invalid as a wrong one
+    number.parse::<u64>().ok()?.checked_mul(secs).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::utils::testing::TempDir;
+
+    use super::{GlobalConfig, HashCheck, Setting, parse_interval};
+
+    #[test]
+    fn test_version_layers() {
+        let mut config = GlobalConfig { file: "config.toml".to_string(), ..Default::default() };
+        config.maven.default = Some("3.8.8".to_string());
+        config.maven.aliases.insert("stable".to_string(), "3.9.*".to_string());
+        let project = Setting::new("stable", "javabox.toml");
+        assert_eq!(Some(Setting::new("3.8.8", "config.toml [maven.default]")), config.version("maven", None, None));
+        let version = config.version("maven", Some(project.clone()), None).unwrap();
+        assert_eq!("3.9.*", version.value);
+        assert_eq!("javabox.toml, alias 'stable' from config.toml [maven.aliases]", version.origin);
+        config.maven.enforce = Some("3.9.6".to_string());
+        assert_eq!(Some(Setting::new("3.9.6", "config.toml [maven.enforce]")), config.version("maven", Some(project), None));
+        assert_eq!(None, config.version("gradle", None, None));
+    }
+
+    #[test]
+    fn test_update_check_interval() {
+        let mut config = GlobalConfig { file: "config.toml".to_string(), ..Default::default() };
+        config.update_check_interval = Some("7d".to_string());
+        config.gradle.update_check_interval = Some("never".to_string());
+        let (interval, setting) = config.update_check_interval("maven").unwrap();
+        assert_eq!(Duration::from_secs(7 * 24 * 3600), interval);
+        assert_eq!("config.toml [update_check_interval]", setting.origin);
+        let (interval, setting) = config.update_check_interval("gradle").unwrap();
+        assert_eq!(Duration::MAX, interval);
+        assert_eq!("config.toml [gradle.update_check_interval]", setting.origin);
+        config.maven.update_check_interval = Some("soon".to_string());
+        assert!(config.update_check_interval("maven").is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let tmp = TempDir::new("global");
+        let config_file = tmp.join("config.toml");
+        std::fs::write(&config_file, "hash_check = \"enforce\"\n\n[java]\nvendor = \"oracle\"\nenforce = \"21\"\ndefault_major = { temurin = \"17\" }\n").unwrap();
+        let config = GlobalConfig::load_file(&config_file).unwrap();
+        assert_eq!(Some(HashCheck::Enforce), config.hash_check);
+        assert_eq!(Some("oracle".to_string()), config.java.vendor);
+        assert_eq!(Some("21".to_string()), config.java.tool.enforce);
+        let origin = format!("{} [java.default_major.temurin]", config_file.display());
+        assert_eq!(Some(Setting::new("17", &origin)), config.java_default_major(|vendor| vendor == "temurin"));
+        assert_eq!("", GlobalConfig::load_file(&tmp.join("missing.toml")).unwrap().file);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(Some(Duration::from_secs(24 * 3600)), parse_interval("24h"));
+        assert_eq!(Some(Duration::from_secs(7 * 24 * 3600)), parse_interval("7d"));
+        assert_eq!(Some(Duration::from_secs(1800)), parse_interval("30m"));
+        assert_eq!(Some(Duration::from_secs(90)), parse_interval("90"));
+        assert_eq!(Some(Duration::MAX), parse_interval("never"));
+        assert_eq!(None, parse_interval("soon"));
+        assert_eq!(None, parse_interval("3w"));
+        assert_eq!(None, parse_interval("999999999999999999d"));
+    }
+}
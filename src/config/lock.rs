@@ -0,0 +1,211 @@
+//! # Lockfile
+//!
+//! `javabox.lock` pins the exact distributions resolved for a project: JDK build, Maven and Gradle versions,
+//! their download URLs and SHA-256 checksums. When present, builds use it instead of resolving again,
+//! so that every machine runs byte-identical toolchains. It is written by `javabox lock`.
+//!
+//! An entry is used for as long as it satisfies the configuration; in locked mode (`--locked` or `JAVABOX_LOCKED=1`),
+//! a missing or outdated entry is an error instead of being resolved again.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_derive::{Deserialize, Serialize};
+use url::Url;
+
+use crate::utils;
+use crate::utils::version::VersionConstraint;
+
+pub const LOCK_NAME: &str = "javabox.lock";
+
+/// Environment variable that enables locked mode, like `JAVABOX_LOCKED=1`
+pub const JAVABOX_LOCKED: &str = "JAVABOX_LOCKED";
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JavaboxLock {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java: Option<LockedDistribution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maven: Option<LockedDistribution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gradle: Option<LockedDistribution>,
+}
+
+/// Exact distribution of a tool
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedDistribution {
+    pub version: String,
+    /// JDK build, like `jdk-17.0.8+7`; it also names the installation directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    pub url: String,
+    pub sha256: String,
+}
+
+/// A distribution in use, that can become a lock entry
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub version: String,
+    pub release: Option<String>,
+    /// unknown for JDKs that were not installed by this version of javabox
+    pub url: Option<String>,
+    /// the downloaded archive, kept next to the installation
+    pub archive: PathBuf,
+    pub sha256: Option<String>,
+}
+
+/// Sources of the tools used by a project
+#[derive(Default, Debug)]
+pub struct Sources {
+    pub java: Option<Source>,
+    pub maven: Option<Source>,
+    pub gradle: Option<Source>,
+}
+
+impl JavaboxLock {
+    /// Loads `javabox.lock` from given directory, if there is one
+    pub fn load(dir: &Path) -> anyhow::Result<Option<Self>> {
+        let lock_file = dir.join(LOCK_NAME);
+        if !lock_file.is_file() {
+            return Ok(None);
+        }
+        log::trace!("JavaboxLock::load({})", lock_file.display());
+        let lock: JavaboxLock = confy::load_path(&lock_file)
+            .map_err(|e| anyhow::anyhow!("Invalid {} :: {e}", lock_file.display()))?;
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        let lock_file = dir.join(LOCK_NAME);
+        log::info!("Writing {}", lock_file.display());
+        confy::store_path(lock_file, self)?;
+        Ok(())
+    }
+
+    fn entry(&self, tool: &str) -> Option<&LockedDistribution> {
+        match tool {
+            "java" => self.java.as_ref(),
+            "maven" => self.maven.as_ref(),
+            "gradle" => self.gradle.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl Source {
+    /// Source of a distribution downloaded from `url`, with the archive kept in `dist_dir`
+    pub fn new(version: &str, url: &str, dist_dir: &Path, sha256: Option<&str>) -> Self {
+        let archive_name = url.rsplit('/').next().unwrap_or_default();
+        Source {
+            version: version.to_string(),
+            release: None,
+            url: Some(url.to_string()),
+            archive: dist_dir.join(archive_name),
+            sha256: sha256.map(str::to_string),
+        }
+    }
+
+    pub fn from_locked(locked: &LockedDistribution, dist_dir: &Path) -> Self {
+        Source {
+            release: locked.release.clone(),
+            ..Self::new(&locked.version, &locked.url, dist_dir, Some(&locked.sha256))
+        }
+    }
+
+    /// Turns into a lock entry; the checksum is computed from the archive when not known, which may need downloading it again.
+    fn lock(&self) -> anyhow::Result<LockedDistribution> {
+        let url = self.url.as_ref()
+            .ok_or(anyhow::anyhow!("Cannot lock {}: it is not known where it was downloaded from; remove it, so that javabox installs it again", self.archive.display()))?;
+        let sha256 = match &self.sha256 {
+            Some(sha256) => sha256.to_ascii_lowercase(),
+            None => {
+                let parsed: Url = url.parse()?;
+                if !self.archive.is_file() {
+                    utils::download_distribution(&parsed, &self.archive, None, None)?;
+                }
+                utils::checksum::file_sha256(&self.archive)?
+            }
+        };
+        Ok(LockedDistribution {
+            version: self.version.clone(),
+            release: self.release.clone(),
+            url: url.clone(),
+            sha256,
+        })
+    }
+}
+
+impl Sources {
+    pub fn lock(&self) -> anyhow::Result<JavaboxLock> {
+        Ok(JavaboxLock {
+            java: self.java.as_ref().map(Source::lock).transpose()?,
+            maven: self.maven.as_ref().map(Source::lock).transpose()?,
+            gradle: self.gradle.as_ref().map(Source::lock).transpose()?,
+        })
+    }
+}
+
+/// Enables locked mode for the rest of this process.
+pub fn set_locked() {
+    log::debug!("Locked mode");
+    LOCKED.store(true, Ordering::Relaxed);
+}
+
+/// In locked mode, every tool must come from an up-to-date `javabox.lock`.
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::Relaxed) || match std::env::var(JAVABOX_LOCKED) {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false" | "no"),
+        Err(_) => false,
+    }
+}
+
+/// The locked distribution of the tool, if it still satisfies the configuration; otherwise it has to be resolved.
+pub fn locked<'a>(lock: Option<&'a JavaboxLock>, tool: &str, satisfies: impl Fn(&str) -> bool) -> anyhow::Result<Option<&'a LockedDistribution>> {
+    match lock.and_then(|lock| lock.entry(tool)) {
+        Some(entry) if satisfies(&entry.version) => {
+            log::debug!("Using {tool} {} locked in {LOCK_NAME}", entry.version);
+            Ok(Some(entry))
+        }
+        Some(entry) if is_locked() => {
+            anyhow::bail!("Locked {tool} {} does not satisfy the configuration anymore; run `javabox lock` to update {LOCK_NAME}", entry.version)
+        }
+        Some(entry) => {
+            log::warn!("Locked {tool} {} does not satisfy the configuration anymore, resolving it again; run `javabox lock` to update {LOCK_NAME}", entry.version);
+            Ok(None)
+        }
+        None if is_locked() => anyhow::bail!("Locked mode: {tool} is not locked in {LOCK_NAME}; run `javabox lock` first"),
+        None => Ok(None),
+    }
+}
+
+/// Decides if an exact version satisfies the configured one, which may be a constraint; empty means any.
+pub fn satisfies(configured: &str, version: &str) -> bool {
+    if VersionConstraint::is_constraint(configured) {
+        VersionConstraint::parse(configured).is_ok_and(|constraint| constraint.matches(version))
+    } else {
+        configured.is_empty() || configured == version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JavaboxLock, LockedDistribution, locked, satisfies};
+
+    #[test]
+    fn test_locked() {
+        let maven = LockedDistribution {
+            version: "3.9.6".to_string(),
+            release: None,
+            url: "https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/3.9.6/apache-maven-3.9.6-bin.zip".to_string(),
+            sha256: "6eedd2cae3626d6ad3a5c9ee324bd265853d64297f07f033430755bd0e0c3a4b".to_string(),
+        };
+        let lock = JavaboxLock { maven: Some(maven.clone()), ..Default::default() };
+        assert_eq!(Some(&maven), locked(Some(&lock), "maven", |version| satisfies("3.9.*", version)).unwrap());
+        assert_eq!(None, locked(Some(&lock), "maven", |version| satisfies("3.8.8", version)).unwrap());
+        assert_eq!(None, locked(Some(&lock), "gradle", |_| true).unwrap());
+        assert_eq!(None, locked(None, "maven", |_| true).unwrap());
+        assert!(satisfies("", "8.5"));
+        assert!(!satisfies("8.5", "8.5.1"));
+    }
+}
@@ -0,0 +1,96 @@
+//! # Command-line overrides
+//!
+//! Tokens like `@@jdk=11::`, `@@jdk:oracle::` or `@@mvn=3.2.1::` can be mixed into the command line of a tool:
+//! ```shell
+//! mvn @@jdk=21:: @@mvn=3.9.6:: clean install
+//! ```
+//! Javabox consumes them before passing the rest to the tool. They win over everything else, even the environment,
+//! so that a build can be tried under another JDK without editing any file.
+//!
+//! The general form is `@@{tool}[:{vendor}][={version}]::`, where the tool is `jdk` (or `java`), `mvn` (or `maven`),
+//! or `gradle`; only the JDK has a vendor.
+use std::ffi::OsString;
+use std::sync::OnceLock;
+
+use crate::config::global::Setting;
+
+static OVERRIDES: OnceLock<Vec<(String, Setting)>> = OnceLock::new();
+
+/// Removes override tokens from the arguments, and keeps them for the rest of this process.
+pub fn extract(args: &[OsString]) -> anyhow::Result<Vec<OsString>> {
+    let mut overrides = Vec::new();
+    let mut remaining = Vec::new();
+    for arg in args {
+        match arg.to_str().and_then(|arg| arg.strip_prefix("@@")?.strip_suffix("::").map(|body| (arg, body))) {
+            Some((token, body)) => overrides.extend(parse(token, body)?),
+            None => remaining.push(arg.clone()),
+        }
+    }
+    for (key, setting) in &overrides {
+        log::debug!("{key} = {} from {}", setting.value, setting.origin);
+    }
+    if OVERRIDES.set(overrides).is_err() {
+        log::warn!("Command-line overrides were already set, ignoring the new ones");
+    }
+    Ok(remaining)
+}
+
+/// The override of a setting, like `java.version` or `maven.version`
+pub fn get(key: &str) -> Option<Setting> {
+    OVERRIDES.get()?.iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, setting)| setting.clone())
+}
+
+/// Parses the body between `@@` and `::` into settings
+fn parse(token: &str, body: &str) -> anyhow::Result<Vec<(String, Setting)>> {
+    let origin = format!("command line {token}");
+    let (name, version) = match body.split_once('=') {
+        Some((name, version)) => (name, Some(version)),
+        None => (body, None),
+    };
+    let (tool, vendor) = match name.split_once(':') {
+        Some((tool, vendor)) => (tool, Some(vendor)),
+        None => (name, None),
+    };
+    let tool = match tool {
+        "jdk" | "java" => "java",
+        "mvn" | "maven" => "maven",
+        "gradle" => "gradle",
+        _ => anyhow::bail!("Unknown tool in {token}; use like @@jdk=17::, @@jdk:oracle::, @@mvn=3.9.6:: or @@gradle=8.5::"),
+    };
+    let mut settings = Vec::new();
+    match vendor {
+        Some(vendor) if tool == "java" && !vendor.is_empty() => settings.push(("java.vendor".to_string(), Setting::new(vendor, &origin))),
+        Some(_) => anyhow::bail!("Invalid vendor in {token}; only the JDK has one, like @@jdk:oracle::"),
+        None => {}
+    }
+    match version {
+        Some(version) if !version.is_empty() => settings.push((format!("{tool}.version"), Setting::new(version, &origin))),
+        Some(_) => anyhow::bail!("Missing version in {token}"),
+        None if settings.is_empty() => anyhow::bail!("Nothing to override in {token}; use like @@jdk=17:: or @@jdk:oracle::"),
+        None => {}
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::global::Setting;
+
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        let origin = |token: &str| format!("command line {token}");
+        assert_eq!(vec![("java.version".to_string(), Setting::new("11", &origin("@@jdk=11::")))], parse("@@jdk=11::", "jdk=11").unwrap());
+        assert_eq!(vec![("java.vendor".to_string(), Setting::new("graal", &origin("@@jdk:graal::")))], parse("@@jdk:graal::", "jdk:graal").unwrap());
+        assert_eq!(2, parse("@@jdk:oracle=17.0.8::", "jdk:oracle=17.0.8").unwrap().len());
+        assert_eq!(vec![("maven.version".to_string(), Setting::new("3.2.1", &origin("@@mvn=3.2.1::")))], parse("@@mvn=3.2.1::", "mvn=3.2.1").unwrap());
+        assert_eq!("gradle.version", parse("@@gradle=~8.5::", "gradle=~8.5").unwrap()[0].0);
+        assert!(parse("@@ant=1.10::", "ant=1.10").is_err());
+        assert!(parse("@@mvn:apache=3::", "mvn:apache=3").is_err());
+        assert!(parse("@@jdk::", "jdk").is_err());
+        assert!(parse("@@jdk=::", "jdk=").is_err());
+    }
+}
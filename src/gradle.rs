@@ -1,140 +1,267 @@
-use std::collections::HashMap;
 use std::env::current_dir;
+use std::ffi::OsString;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::Duration;
 
 use dir::home_dir;
 use serde_derive::Deserialize;
 use url::Url;
 use crate::config::{GradleConfig, JavaboxConfig, JavaConfig};
-use crate::utils;
+use crate::config::env::ProjectEnv;
+use crate::config::global::{self, GlobalConfig, Setting};
+use crate::config::lock::{self, JavaboxLock, Source, Sources};
+use crate::gradle::wrapper::{LocalDistribution, WrapperConfiguration};
+use crate::{jdk, utils};
 
-use crate::utils::{download, download_or_reuse};
+use crate::utils::archive::{ArchiveFormat, extract_archive};
+use crate::utils::{download_distribution, download_or_reuse};
+use crate::utils::version::{ComparableVersion, VersionConstraint};
 
 const GRADLE_DIST_URL_BASE: &str = "https://services.gradle.org/distributions";
 // + '"/gradle-6.5-all.zip"
 const GRADLE_DIST_CURRENT_VERSION: &str = "https://services.gradle.org/versions/current"; // JSON
+const GRADLE_DIST_ALL_VERSIONS: &str = "https://services.gradle.org/versions/all"; // JSON
 
-pub fn run_gradle() -> std::io::Result<i32> {
-    // TODO lookup settings.gradle, gradle.properties, if useful
-    let current_dir = current_dir()?;
-    let user_home = home_dir().expect("There is no HOME directory?!");
-    // all ancestors containing modules
-    let mut modules = Vec::new();
-    // top of the SCM repository
-    let mut scm_repo_root = None;
-    let mut wrapper_base = None; // the dir containing wrapper script
-    let mut wrapper_properties = HashMap::new();
-    for d in current_dir.ancestors() {
-        if scm_repo_root.is_none() {
-            // we only care about these files _within_ scm repo, if one exists
-            // ... and also _within_ wrapper, if one exists
-            if wrapper_base.is_none() {
-                if d.join("build.gradle").is_file()
-                    || d.join("build.gradle.kts").is_file() {
+/// Environment variable of Gradle, overriding `~/.gradle`
+const GRADLE_USER_HOME: &str = "GRADLE_USER_HOME";
+
+pub fn run_gradle(args: &[OsString]) -> anyhow::Result<i32> {
+    let gradle_env = GradleEnv::load_or_infer(&current_dir()?)?;
+    let exit_code = gradle_env.execute(args)?;
+    Ok(exit_code)
+}
+
+/// Resolved tooling of a Gradle project
+pub struct GradleEnv {
+    pub gradle_home: PathBuf,
+    pub java_home: Option<PathBuf>,
+    /// the root project
+    project_dir: PathBuf,
+    /// the (sub)project where gradle was invoked
+    module_dir: PathBuf,
+    sources: Sources,
+    /// `[env]` and `[gradle.opts]` of `javabox.toml`
+    pub env: ProjectEnv,
+}
+
+impl GradleEnv {
+    pub fn load_or_infer(current_dir: &Path) -> anyhow::Result<GradleEnv> {
+        Self::resolve(current_dir, false)
+    }
+
+    /// Resolves the tools, preferring those locked in `javabox.lock` of the root project unless updating it.
+    pub fn resolve(current_dir: &Path, update: bool) -> anyhow::Result<GradleEnv> {
+        // TODO lookup settings.gradle, gradle.properties, if useful
+        let user_home = home_dir().expect("There is no HOME directory?!");
+        let gradle_user_home = gradle_user_home();
+        // all ancestors containing modules
+        let mut modules = Vec::new();
+        // top of the SCM repository
+        let mut scm_repo_root = None;
+        let mut wrapper_base = None; // the dir containing wrapper script
+        for d in current_dir.ancestors() {
+            if scm_repo_root.is_none() {
+                // we only care about these files _within_ scm repo, if one exists
+                // ... and also _within_ wrapper, if one exists
+                if wrapper_base.is_none() && is_gradle_project(d) {
                     modules.push(d);
                     log::trace!("Module: {}", d.display());
                 }
+                if d.join("gradlew").is_file()
+                    || d.join("gradlew.bat").is_file()
+                    || d.join("gradle/wrapper").is_dir()
+                {
+                    wrapper_base = Some(d);
+                    log::trace!("WRAPPER: {}", d.display());
+                }
+                //
             }
-            if d.join("gradlew").is_file()
-                || d.join("gradlew.bat").is_file()
-                || d.join("gradle/wrapper").is_dir()
-            {
-                wrapper_base = Some(d);
-                log::trace!("WRAPPER: {}", d.display());
+            if utils::is_scm_wc_root(d) {
+                scm_repo_root = Some(d);
+                log::trace!("SCM WORKING COPY: {}", d.display());
             }
-            //
-        }
-        if utils::is_scm_wc_root(d) {
-            scm_repo_root = Some(d);
-            log::trace!("SCM WORKING COPY: {}", d.display());
-        }
 
-        // stop scan at user home level
-        if d == user_home {
-            break;
+            // stop scan at user home level
+            if d == user_home {
+                break;
+            }
         }
-    }
 
-    // TODO: estimate JDK version and use it as JAVA_HOME and in PATH
+        // for use outside existing modules
+        let project_dir = *modules.last().unwrap_or(&current_dir);
+        let module_dir = *modules.first().unwrap_or(&current_dir);
 
-    // TODO: consider delegating to the existing wrapper, if it isn't myself
-    // estimate gradle version and use it
-    let distribution_url = match wrapper_base {
-        Some(wrapper_base) => {
-            let props = wrapper_base.join("gradle/wrapper/gradle-wrapper.properties");
-            if props.exists() {
-                utils::read_properties(&mut wrapper_properties, &props)?;
-            }
-            let url = wrapper_properties.get("distributionUrl");
-            if let Some(url) = url {
-                if !url.starts_with(GRADLE_DIST_URL_BASE) {
-                    log::warn!("Suspicious: this is not our known Gradle distribution location: {url}");
-                    // if we ever implement a paranoid mode, this could be a reason to stop
-                }
+        let wrapper_dir = wrapper_base.unwrap_or(project_dir);
+        let config = JavaboxConfig::load_or_infer(project_dir, "gradle", |dir| infer_config_with_wrapper(dir, wrapper_dir))?;
+        let lock = if update { None } else { JavaboxLock::load(project_dir)? };
+        let java = match jdk::java_requirement(project_dir, config.java.as_ref(), &config.origin)? {
+            None => None,
+            Some(java) => Some(jdk::resolve_java(&java, lock.as_ref())?),
+        };
+        let (java_home, java_source) = java.unzip();
+        let env = ProjectEnv::resolve(&config, config.gradle.as_ref().map(|gradle| &gradle.opts), project_dir)?;
+
+        // TODO: consider delegating to the existing wrapper, if it isn't myself
+        // estimate gradle version and use it
+        let wrapper = match wrapper_base {
+            Some(wrapper_base) => WrapperConfiguration::load(wrapper_base)?,
+            None => WrapperConfiguration::new(project_dir),
+        };
+        let distribution_url = wrapper.distribution_url.as_ref();
+        if let Some(url) = distribution_url {
+            if !url.starts_with(GRADLE_DIST_URL_BASE) {
+                log::warn!("Suspicious: this is not our known Gradle distribution location: {url}");
+                // if we ever implement a paranoid mode, this could be a reason to stop
             }
-            url
         }
-        None => None
-    };
-    let distribution_url = match distribution_url {
-        None => find_latest_gradle_distribution(&user_home)?, // default=latest if not configured otherwise
-        Some(distribution_url) => distribution_url.clone()
-    };
-    let gradle_home = get_gradle_home(&user_home, &distribution_url)?;
-    log::debug!("Gradle home: {}", gradle_home.display());
-    let launcher = gradle_home.join("bin/gradle");
+        // environment and user configuration may override the project's version, and the wrapper with it
+        let project_version = config.gradle.as_ref()
+            .filter(|gradle| !gradle.version.is_empty())
+            .map(|gradle| Setting::new(&gradle.version, &config.origin));
+        let effective_version = GlobalConfig::get()?.version("gradle", project_version.clone(), None);
+        let overridden = effective_version != project_version;
+        let gradle_version = effective_version.as_ref().map(|setting| setting.value.as_str()).unwrap_or_default();
+        let locked = lock::locked(lock.as_ref(), "gradle", |version| lock::satisfies(gradle_version, version))?;
+        let distribution_url = if let Some(locked) = locked {
+            locked.url.clone()
+        } else if VersionConstraint::is_constraint(gradle_version) {
+            // the wrapper's distribution is preferred while it satisfies the constraint
+            let constraint = VersionConstraint::parse(gradle_version)?;
+            match distribution_url {
+                Some(url) if gradle_version_from_url(url).is_some_and(|version| constraint.matches(&version)) => url.clone(),
+                _ => find_gradle_distribution(&gradle_user_home, &constraint)?,
+            }
+        } else {
+            match distribution_url {
+                Some(distribution_url) if !overridden => distribution_url.clone(),
+                _ if !gradle_version.is_empty() => format!("{GRADLE_DIST_URL_BASE}/gradle-{gradle_version}-bin.zip"),
+                _ => find_latest_gradle_distribution(&gradle_user_home)?, // default=latest if not configured otherwise
+            }
+        };
+        // the pinned checksum only applies to the wrapper's own distribution
+        let distribution_sha256_sum = match locked {
+            Some(locked) => Some(locked.sha256.as_str()),
+            None => wrapper.distribution_sha256_sum.as_deref()
+                .filter(|_| wrapper.distribution_url.as_ref() == Some(&distribution_url)),
+        };
+        let local = wrapper.local_distribution(&distribution_url, &gradle_user_home)?;
+        let gradle_home = get_gradle_home(&wrapper, &local, &distribution_url, distribution_sha256_sum)?;
+        log::debug!("Gradle home: {}", gradle_home.display());
+        let dist_dir = local.zip_file.parent().unwrap_or(&local.dist_dir);
+        let gradle_source = match locked {
+            Some(locked) => Source::from_locked(locked, dist_dir),
+            None => {
+                let version = gradle_version_from_url(&distribution_url).unwrap_or_default();
+                Source::new(&version, &distribution_url, dist_dir, distribution_sha256_sum)
+            }
+        };
+        Ok(GradleEnv {
+            gradle_home,
+            java_home,
+            project_dir: project_dir.to_path_buf(),
+            module_dir: module_dir.to_path_buf(),
+            sources: Sources { java: java_source, gradle: Some(gradle_source), maven: None },
+            env,
+        })
+    }
 
-    let current_dir = current_dir.as_path(); // for use outside existing modules
-    let project_dir = *modules.last().unwrap_or(&current_dir);
-    let module_dir = *modules.first().unwrap_or(&current_dir);
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    /// The lock entries of the tools in use
+    pub fn lock(&self) -> anyhow::Result<JavaboxLock> {
+        self.sources.lock()
+    }
 
-    utils::execute_tool(&project_dir, &launcher.display().to_string(), &module_dir)
+    pub fn execute(&self, args: &[OsString]) -> std::io::Result<i32> {
+        let launcher = self.gradle_home.join("bin/gradle");
+        utils::execute_tool(&self.project_dir, &launcher.display().to_string(), &self.module_dir, self.java_home.as_deref(), &self.env, args)
+    }
 }
 
-fn get_gradle_home(user_home: &Path, distribution_url: &String) -> std::io::Result<PathBuf> {
-    let distribution_url = Url::from_str(distribution_url)
-        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
-    let upath = distribution_url.path();
+/// `~/.gradle`, unless `GRADLE_USER_HOME` says otherwise
+pub fn gradle_user_home() -> PathBuf {
+    match std::env::var_os(GRADLE_USER_HOME).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir().expect("There is no HOME directory?!").join(".gradle"),
+    }
+}
 
-    match upath.rfind('/') {
-        None => {
-            log::warn!("Invalid distribution URL: {distribution_url}");
-            Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange distribution URL: {distribution_url}")))
-        }
-        Some(n) => {
-            let zip_name = &upath[n + 1..];
-            let base_name = zip_name.replace(".zip", "");
-            let dist_name = base_name.replace("-bin", "");
-            let url_hash = utils::md5decimal(distribution_url.as_str());
-            let gradle_base = user_home.join(format!(".gradle/wrapper/dists/{base_name}/{url_hash}"));
-            let gradle_home = gradle_base.join(dist_name);
-            if !gradle_home.is_dir() {
-                let zip_path = gradle_base.join(zip_name);
-                // if the zip is missing, download it first (verify checksums!)
-                if !zip_path.is_file() {
-                    std::fs::create_dir_all(gradle_base)?;
-                    download(&distribution_url, &zip_path)?;
-                }
-                let zip = std::fs::File::open(&zip_path)?;
-                zip_extract::extract(zip, &gradle_home, true)
-                    .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("Failed to extract file: {} :: {e:?}", zip_path.display())))?;
-            }
-            Ok(gradle_home)
+/// Installs the distribution like the official wrapper does, so that both share installations.
+fn get_gradle_home(wrapper: &WrapperConfiguration, local: &LocalDistribution, distribution_url: &str, distribution_sha256_sum: Option<&str>) -> std::io::Result<PathBuf> {
+    if let Some(gradle_home) = local.gradle_home() {
+        return Ok(gradle_home);
+    }
+    let url = Url::from_str(distribution_url)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
+    // if the archive is missing, download it first
+    if !local.zip_file.is_file() {
+        if !utils::is_offline() {
+            wrapper.validate(&url)?;
         }
+        std::fs::create_dir_all(local.zip_file.parent().unwrap())?;
+        download_distribution(&url, &local.zip_file, distribution_sha256_sum, Some(wrapper.network_timeout))?;
+    }
+    // distributions contain a single directory, like `gradle-8.5`
+    let dist_name = match gradle_version_from_url(distribution_url) {
+        Some(version) => format!("gradle-{version}"),
+        None => ArchiveFormat::base_name(&local.zip_file.file_name().unwrap_or_default().to_string_lossy()).to_string(),
+    };
+    let gradle_home = local.dist_dir.join(dist_name);
+    // extracted next to the distribution directory, so that neither javabox nor the wrapper sees it half-filled
+    let tmp_dir = local.dist_dir.with_extension(format!("{}.part", std::process::id()));
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+    if let Err(e) = extract_archive(&local.zip_file, &tmp_dir) {
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+        return Err(e);
+    }
+    // without the marker, what is there is left over from an interrupted installation, like the wrapper says
+    if let Some(gradle_home) = local.gradle_home() {
+        // installed meanwhile by another process
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+        return Ok(gradle_home);
+    }
+    for dir in std::fs::read_dir(&local.dist_dir).into_iter().flatten().flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+        log::debug!("Removing incomplete installation {}", dir.display());
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(&local.dist_dir)?;
+    std::fs::rename(&tmp_dir, &gradle_home)?;
+    std::fs::File::create(local.marker_file())?;
+    Ok(gradle_home)
+}
+
+/// Checks for new Gradle releases now, regardless of the update check interval
+pub fn refresh_versions() -> std::io::Result<()> {
+    let gradle_user_home = gradle_user_home();
+    for (url, path) in [current_version_location(&gradle_user_home)?, all_versions_location(&gradle_user_home)?] {
+        utils::refresh(&url, &path)?;
     }
+    Ok(())
 }
 
-fn find_latest_gradle_distribution(user_home: &Path) -> std::io::Result<String> {
-    let metadata_xml = user_home.join(".gradle/wrapper/dists/current");
+fn current_version_location(gradle_user_home: &Path) -> std::io::Result<(Url, PathBuf)> {
     let url = GRADLE_DIST_CURRENT_VERSION;
     let url = Url::from_str(url)
         .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Failed to download maven-matadata.xml: {} :: {e:?}", url)))?;
+    Ok((url, gradle_user_home.join("wrapper/dists/current")))
+}
+
+fn all_versions_location(gradle_user_home: &Path) -> std::io::Result<(Url, PathBuf)> {
+    let url = Url::from_str(GRADLE_DIST_ALL_VERSIONS)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Failed to download Gradle versions: {GRADLE_DIST_ALL_VERSIONS} :: {e:?}")))?;
+    Ok((url, gradle_user_home.join("wrapper/dists/versions-all.json")))
+}
+
+fn find_latest_gradle_distribution(gradle_user_home: &Path) -> std::io::Result<String> {
+    if utils::is_offline() {
+        return newest_installed_gradle(gradle_user_home);
+    }
+    let (url, metadata_xml) = current_version_location(gradle_user_home)?;
     // reuse the file for some time, they don't release maven every hour
-    // one day should be good enough
-    download_or_reuse(&url, &metadata_xml, Duration::from_secs(3600 * 24))?;
+    download_or_reuse(&url, &metadata_xml, global::update_check_interval("gradle")?)?;
     // extract the latest version
     let current_gradle = std::fs::File::open(&metadata_xml)?;
     let current_gradle: CurrentVersionJson = serde_json::from_reader(current_gradle)
@@ -144,14 +271,77 @@ fn find_latest_gradle_distribution(user_home: &Path) -> std::io::Result<String>
     Ok(current_gradle.download_url)
 }
 
-pub fn infer_config(_cwd: &Path) -> anyhow::Result<JavaboxConfig> {
+/// Distribution URL of the newest Gradle that satisfies the constraint, considering installed and released versions.
+fn find_gradle_distribution(gradle_user_home: &Path, constraint: &VersionConstraint) -> std::io::Result<String> {
+    let mut candidates: Vec<String> = installed_gradles(gradle_user_home).into_iter().map(|(version, _)| version).collect();
+    if !utils::is_offline() {
+        candidates.extend(released_gradles(gradle_user_home)?);
+    }
+    match constraint.best(candidates.iter().map(String::as_str)) {
+        Some(version) => {
+            log::debug!("Gradle {version} satisfies '{constraint}'");
+            Ok(format!("{GRADLE_DIST_URL_BASE}/gradle-{version}-bin.zip"))
+        }
+        None => Err(constraint.unsatisfied("Gradle", &candidates)),
+    }
+}
+
+/// Versions of all Gradle releases, excluding release candidates, milestones and nightly builds
+fn released_gradles(gradle_user_home: &Path) -> std::io::Result<Vec<String>> {
+    let (url, versions_json) = all_versions_location(gradle_user_home)?;
+    download_or_reuse(&url, &versions_json, global::update_check_interval("gradle")?)?;
+    let versions: Vec<VersionJson> = serde_json::from_reader(std::fs::File::open(&versions_json)?)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("Invalid format of {} :: {e:?}", versions_json.display())))?;
+    Ok(versions.into_iter()
+        .filter(|v| !v.snapshot && !v.nightly && !v.broken && v.rc_for.is_empty() && v.milestone_for.is_empty())
+        .map(|v| v.version)
+        .collect())
+}
+
+/// Versions and distribution URLs of Gradle installed from the default location
+fn installed_gradles(gradle_user_home: &Path) -> Vec<(String, String)> {
+    let dists = gradle_user_home.join("wrapper/dists");
+    let wrapper = WrapperConfiguration::new(gradle_user_home);
+    std::fs::read_dir(dists).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let version = name.strip_prefix("gradle-")?.strip_suffix("-bin")?.to_string();
+            let url = format!("{GRADLE_DIST_URL_BASE}/{name}.zip");
+            let local = wrapper.local_distribution(&url, gradle_user_home).ok()?;
+            local.gradle_home().map(|_| (version, url))
+        })
+        .collect()
+}
+
+/// Distribution URL of the newest Gradle installed from the default location; used in offline mode.
+fn newest_installed_gradle(gradle_user_home: &Path) -> std::io::Result<String> {
+    installed_gradles(gradle_user_home).into_iter()
+        .max_by_key(|(version, _)| ComparableVersion::new(version))
+        .map(|(_, url)| url)
+        .ok_or(std::io::Error::new(ErrorKind::NotFound, format!("Offline mode: no Gradle distribution installed in {}", gradle_user_home.join("wrapper/dists").display())))
+}
+
+pub fn infer_config(cwd: &Path) -> anyhow::Result<JavaboxConfig> {
+    infer_config_with_wrapper(cwd, cwd)
+}
+
+/// Infers the configuration of the project in given directory, whose wrapper may be in an ancestor directory.
+fn infer_config_with_wrapper(cwd: &Path, wrapper_dir: &Path) -> anyhow::Result<JavaboxConfig> {
     log::debug!("infer gradle configuration");
-    //TODO: implement more sophisticated/precise detection
-    let gradle = Some(GradleConfig {
-        version: "8.3".to_string()
+    let distribution_url = match WrapperConfiguration::load(wrapper_dir)?.distribution_url {
+        Some(distribution_url) => distribution_url,
+        None => find_latest_gradle_distribution(&gradle_user_home())?,
+    };
+    // a mirror or custom distribution may be named differently; the wrapper's URL is then used as it is
+    let gradle_version = gradle_version_from_url(&distribution_url).unwrap_or_else(|| {
+        log::debug!("Cannot determine Gradle version from distribution URL: {distribution_url}");
+        String::new()
     });
+    let java_version = infer_java_version(cwd)?;
+    let gradle = Some(GradleConfig::from(gradle_version));
     let java = Some(JavaConfig {
-        version: "8".to_string(),
+        version: java_version,
+        vendor: None,
     });
     Ok(JavaboxConfig {
         java,
@@ -160,6 +350,28 @@ pub fn infer_config(_cwd: &Path) -> anyhow::Result<JavaboxConfig> {
     })
 }
 
+/// Infers the Java version from build scripts of the project in given directory.
+pub fn infer_java_version(cwd: &Path) -> std::io::Result<String> {
+    match buildfile::infer_java_version(cwd)? {
+        Some(java_version) => Ok(java_version),
+        None => {
+            log::debug!("No java version in build scripts of {}, using default {}", cwd.display(), jdk::DEFAULT_JAVA_VERSION);
+            Ok(jdk::DEFAULT_JAVA_VERSION.to_string())
+        }
+    }
+}
+
+/// Extracts version from distribution file name, like `gradle-8.5-bin.zip` or `gradle-7.2-all.zip`
+fn gradle_version_from_url(distribution_url: &str) -> Option<String> {
+    let file_name = distribution_url.rsplit('/').next()?;
+    let base_name = ArchiveFormat::base_name(file_name);
+    let version = base_name.strip_prefix("gradle-")?;
+    let version = version.strip_suffix("-bin")
+        .or(version.strip_suffix("-all"))
+        .unwrap_or(version);
+    Some(version.to_string())
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(rename = "metadata")]
 #[serde(rename_all = "camelCase")]
@@ -169,3 +381,75 @@ struct CurrentVersionJson {
     download_url: String,
     checksum_url: String,
 }
+
+/// Item of the `versions/all` JSON
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionJson {
+    version: String,
+    #[serde(default)]
+    snapshot: bool,
+    #[serde(default)]
+    nightly: bool,
+    #[serde(default)]
+    broken: bool,
+    #[serde(default)]
+    rc_for: String,
+    #[serde(default)]
+    milestone_for: String,
+}
+
+mod buildfile;
+mod wrapper;
+pub use buildfile::is_gradle_project;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::utils::testing::TempDir;
+
+    use super::{get_gradle_home, gradle_version_from_url, infer_config};
+    use super::wrapper::{LocalDistribution, WrapperConfiguration, WRAPPER_PROPERTIES};
+
+    #[test]
+    fn test_gradle_version_from_url() {
+        assert_eq!(Some("8.5".to_string()), gradle_version_from_url("https://services.gradle.org/distributions/gradle-8.5-bin.zip"));
+        assert_eq!(Some("7.2".to_string()), gradle_version_from_url("https://services.gradle.org/distributions/gradle-7.2-all.zip"));
+        assert_eq!(Some("8.6-rc-1".to_string()), gradle_version_from_url("https://nexus.local/gradle/gradle-8.6-rc-1-bin.zip"));
+        assert_eq!(None, gradle_version_from_url("https://nexus.local/gradle/dist.zip"));
+    }
+
+    #[test]
+    fn test_infer_config_custom_distribution() {
+        let project_dir = TempDir::new("gradle-infer");
+        let properties_file = project_dir.join(WRAPPER_PROPERTIES);
+        std::fs::create_dir_all(properties_file.parent().unwrap()).unwrap();
+        std::fs::write(&properties_file, "distributionUrl=https\\://nexus.local/dist/acme-build-2.1.zip\n").unwrap();
+        std::fs::write(project_dir.join("build.gradle"), "").unwrap();
+        let config = infer_config(&project_dir).unwrap();
+        assert_eq!("", config.gradle.unwrap().version);
+    }
+
+    #[test]
+    fn test_get_gradle_home_replaces_incomplete() {
+        let tmp = TempDir::new("gradle-install");
+        let local = LocalDistribution {
+            dist_dir: tmp.join("dists/gradle-8.5-bin/hash"),
+            zip_file: tmp.join("dists/gradle-8.5-bin/hash/gradle-8.5-bin.zip"),
+        };
+        // left over by an interrupted installation
+        std::fs::create_dir_all(local.dist_dir.join("gradle-8.5/lib")).unwrap();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&local.zip_file).unwrap());
+        zip.start_file("gradle-8.5/bin/gradle", Default::default()).unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        zip.finish().unwrap();
+        let url = "https://services.gradle.org/distributions/gradle-8.5-bin.zip";
+        let gradle_home = get_gradle_home(&WrapperConfiguration::new(&tmp), &local, url, None).unwrap();
+        assert_eq!(local.dist_dir.join("gradle-8.5"), gradle_home);
+        assert!(gradle_home.join("bin/gradle").is_file());
+        assert!(!gradle_home.join("lib").exists());
+        assert!(local.marker_file().is_file());
+        assert_eq!(1, std::fs::read_dir(tmp.join("dists/gradle-8.5-bin")).unwrap().count());
+    }
+}
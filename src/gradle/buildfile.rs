@@ -0,0 +1,235 @@
+//! # Gradle build scripts
+//!
+//! Build scripts are programs, so javabox does not evaluate them - it only recognizes the common ways
+//! of declaring the Java version, in both Groovy (`build.gradle`) and Kotlin (`build.gradle.kts`) DSL:
+//! ```kotlin
+//! java { toolchain { languageVersion = JavaLanguageVersion.of(17) } }
+//! kotlin { jvmToolchain(17) }
+//! java { sourceCompatibility = JavaVersion.VERSION_17 }
+//! sourceCompatibility = '1.8'
+//! tasks.withType<JavaCompile> { options.release = 17 }
+//! ```
+use std::path::{Path, PathBuf};
+
+use crate::config::cache;
+
+pub const BUILD_FILES: [&str; 2] = ["build.gradle", "build.gradle.kts"];
+pub const SETTINGS_FILES: [&str; 2] = ["settings.gradle", "settings.gradle.kts"];
+
+/// Subdirectories never containing build scripts of the project
+const SKIPPED_DIRS: [&str; 4] = ["build", "out", "node_modules", "src"];
+
+/// How deep to look for build scripts of subprojects and convention plugins
+const MAX_DEPTH: usize = 5;
+
+/// Declarations in order of preference: toolchain is what Gradle actually uses to compile
+const TOOLCHAIN_MARKERS: [&str; 2] = ["JavaLanguageVersion.of(", "jvmToolchain("];
+const COMPATIBILITY_MARKERS: [&str; 3] = ["release", "sourceCompatibility", "targetCompatibility"];
+
+/// Decides if the directory contains a Gradle build.
+pub fn is_gradle_project(dir: &Path) -> bool {
+    BUILD_FILES.iter().chain(SETTINGS_FILES.iter())
+        .any(|name| dir.join(name).is_file())
+}
+
+/// Infers the Java version from build scripts of the project rooted at given directory.
+/// The root build script is examined first, then those of subprojects and `buildSrc` convention plugins.
+pub fn infer_java_version(project_dir: &Path) -> std::io::Result<Option<String>> {
+    let mut scripts: Vec<PathBuf> = BUILD_FILES.iter()
+        .map(|name| project_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    collect_nested_scripts(project_dir, 0, &mut scripts);
+    for script in scripts {
+        cache::note_input(&script);
+        let text = std::fs::read_to_string(&script)?;
+        if let Some(version) = java_version(&text) {
+            log::debug!("Java version {version} found in {}", script.display());
+            return Ok(Some(version));
+        }
+    }
+    Ok(None)
+}
+
+fn collect_nested_scripts(dir: &Path, depth: usize, scripts: &mut Vec<PathBuf>) {
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if path.is_dir() {
+            if name.starts_with('.') || SKIPPED_DIRS.contains(&name) {
+                // ... except for convention plugins, that live in `buildSrc/src/main/groovy` etc.
+                if !(name == "src" && dir.ends_with("buildSrc")) {
+                    continue;
+                }
+            }
+            collect_nested_scripts(&path, depth + 1, scripts);
+        } else if depth > 0 && (name.ends_with(".gradle") || name.ends_with(".gradle.kts")) && !SETTINGS_FILES.contains(&name) {
+            scripts.push(path);
+        }
+    }
+}
+
+/// Finds Java version declared in a build script.
+pub fn java_version(script: &str) -> Option<String> {
+    let script = strip_comments(script);
+    for marker in TOOLCHAIN_MARKERS {
+        for (n, _) in script.match_indices(marker) {
+            if !is_whole_word(&script, n, marker) {
+                continue;
+            }
+            if let Some(version) = parse_version_value(&script[n + marker.len()..]) {
+                return Some(version);
+            }
+        }
+    }
+    for marker in COMPATIBILITY_MARKERS {
+        for (n, _) in script.match_indices(marker) {
+            if !is_whole_word(&script, n, marker) {
+                continue;
+            }
+            let rest = &script[n + marker.len()..];
+            // `release` is a popular name, like in `ext.release = '2.0'`; only the compile option counts
+            if marker == "release" && !(script[..n].ends_with("options.") || rest.starts_with(".set(")) {
+                continue;
+            }
+            let rest = rest.trim_start();
+            // assignment (`=`), setter (`.set(`), or groovy method call without parentheses
+            let rest = if let Some(rest) = rest.strip_prefix('=') {
+                rest
+            } else if let Some(rest) = rest.strip_prefix(".set(") {
+                rest
+            } else if rest.starts_with(|c: char| c == '\'' || c == '"' || c.is_ascii_digit() || c == 'J') {
+                rest
+            } else {
+                continue;
+            };
+            if let Some(version) = parse_version_value(rest) {
+                return Some(version);
+            }
+        }
+    }
+    None
+}
+
+/// Decides if the marker found at given position is not just a part of a longer name
+fn is_whole_word(script: &str, n: usize, marker: &str) -> bool {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    let before = script[..n].chars().next_back();
+    // markers like `jvmToolchain(` end with their own delimiter
+    let after = script[n + marker.len()..].chars().next().filter(|_| !marker.ends_with('('));
+    !before.is_some_and(is_name_char) && !after.is_some_and(is_name_char)
+}
+
+/// Parses a version expression at the beginning of given text:
+/// `17`, `'1.8'`, `"17"`, `JavaVersion.VERSION_1_8`, `JavaVersion.toVersion(17)`, `JavaLanguageVersion.of(21)`
+fn parse_version_value(text: &str) -> Option<String> {
+    let value = text.lines().next()?;
+    let value = value.split([')', ';', '}', ',']).next()?.trim();
+    let value = match value.find("VERSION_") {
+        Some(n) => value[n + "VERSION_".len()..].replace('_', "."),
+        None => value.trim_start_matches("JavaVersion.toVersion(")
+            .trim_start_matches("JavaLanguageVersion.of(")
+            .trim_matches(|c| c == '\'' || c == '"' || c == ' ')
+            .to_string(),
+    };
+    let version: String = value.chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let version = version.trim_end_matches('.');
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+/// Removes `//` and `/* */` comments, leaving string literals alone.
+fn strip_comments(script: &str) -> String {
+    let mut result = String::with_capacity(script.len());
+    let mut chars = script.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                result.push(c);
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        result.push(escaped);
+                    }
+                } else if c == q || c == '\n' {
+                    quote = None;
+                }
+            }
+            None => match (c, chars.peek()) {
+                ('/', Some('/')) => {
+                    while chars.next_if(|&c| c != '\n').is_some() {}
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    let mut prev = ' ';
+                    for c in chars.by_ref() {
+                        if prev == '*' && c == '/' {
+                            break;
+                        }
+                        prev = c;
+                    }
+                    result.push(' ');
+                }
+                ('\'' | '"', _) => {
+                    quote = Some(c);
+                    result.push(c);
+                }
+                _ => result.push(c),
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::java_version;
+
+    #[test]
+    fn test_java_version() {
+        assert_eq!(Some("17".to_string()), java_version(r#"
+plugins { id 'java' }
+java {
+    toolchain {
+        languageVersion = JavaLanguageVersion.of(17)
+    }
+}"#));
+        assert_eq!(Some("21".to_string()), java_version(r#"
+java {
+    toolchain {
+        languageVersion.set(JavaLanguageVersion.of("21"))
+    }
+}"#));
+        assert_eq!(Some("17".to_string()), java_version("kotlin {\n    jvmToolchain(17)\n}\n"));
+        assert_eq!(Some("1.8".to_string()), java_version("sourceCompatibility = '1.8'\ntargetCompatibility = '1.8'\n"));
+        assert_eq!(Some("1.8".to_string()), java_version("sourceCompatibility 1.8\n"));
+        assert_eq!(Some("1.8".to_string()), java_version("java {\n  sourceCompatibility = JavaVersion.VERSION_1_8\n}\n"));
+        assert_eq!(Some("11".to_string()), java_version("java.sourceCompatibility = JavaVersion.VERSION_11\n"));
+        assert_eq!(Some("17".to_string()), java_version("java {\n  targetCompatibility.set(JavaVersion.toVersion(17))\n}\n"));
+        assert_eq!(Some("21".to_string()), java_version("tasks.withType<JavaCompile> {\n  options.release.set(21)\n}\n"));
+        assert_eq!(Some("11".to_string()), java_version("// sourceCompatibility = '1.8'\n/* languageVersion = JavaLanguageVersion.of(8) */\nsourceCompatibility = 11\n"));
+        assert_eq!(None, java_version("plugins { id 'java' }\nversion = '1.0.0'\nreleaseNotes = 'x'\n"));
+        assert_eq!(Some("17".to_string()), java_version("compileJava.options.release = 17\n"));
+        assert_eq!(Some("17".to_string()), java_version("tasks.compileJava { options { release.set(17) } }\n"));
+    }
+
+    #[test]
+    fn test_java_version_other_names() {
+        assert_eq!(None, java_version("ext.release = \"2.0\"\n"));
+        assert_eq!(None, java_version("def release = \"1.2\"\nversion = release\n"));
+        assert_eq!(None, java_version("release 3\nrelease2 = 4\n"));
+        assert_eq!(None, java_version("sourceCompatibilityLevel = 5\nMyJavaLanguageVersion.of(8)\n"));
+    }
+}
@@ -0,0 +1,241 @@
+//! # Gradle Wrapper configuration
+//!
+//! `gradle/wrapper/gradle-wrapper.properties` is read the same way as the official wrapper does:
+//! ```properties
+//! distributionBase=GRADLE_USER_HOME
+//! distributionPath=wrapper/dists
+//! distributionUrl=https\://services.gradle.org/distributions/gradle-8.5-bin.zip
+//! distributionSha256Sum=9d926787066a081739e8200858338b4a69e837c3a821a33aca9db09dd4a41026
+//! networkTimeout=10000
+//! validateDistributionUrl=true
+//! zipStoreBase=GRADLE_USER_HOME
+//! zipStorePath=wrapper/dists
+//! ```
+//! A distribution is unpacked into `{distributionBase}/{distributionPath}/{name}/{hash}`, and its archive is kept in
+//! `{zipStoreBase}/{zipStorePath}/{name}/{hash}`, where the base is either `GRADLE_USER_HOME` or `PROJECT`,
+//! the directory containing `gradle/wrapper`.
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use url::Url;
+
+use crate::utils;
+
+pub const WRAPPER_PROPERTIES: &str = "gradle/wrapper/gradle-wrapper.properties";
+
+const DEFAULT_DISTRIBUTION_PATH: &str = "wrapper/dists";
+const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_millis(10000);
+
+/// Base directory of distributions and their archives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Base {
+    GradleUserHome,
+    Project,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrapperConfiguration {
+    /// relative URLs are already resolved against the properties file
+    pub distribution_url: Option<String>,
+    pub distribution_sha256_sum: Option<String>,
+    distribution_base: Base,
+    distribution_path: String,
+    zip_base: Base,
+    zip_path: String,
+    pub network_timeout: Duration,
+    /// check the URL before downloading, to fail early with a clear message
+    pub validate_distribution_url: bool,
+    /// the `PROJECT` base
+    project_dir: PathBuf,
+}
+
+/// Where a distribution is installed
+#[derive(Debug, PartialEq)]
+pub struct LocalDistribution {
+    /// contains the single directory with the unpacked distribution
+    pub dist_dir: PathBuf,
+    pub zip_file: PathBuf,
+}
+
+impl Base {
+    fn parse(text: &str) -> std::io::Result<Self> {
+        match text.trim() {
+            "GRADLE_USER_HOME" => Ok(Base::GradleUserHome),
+            "PROJECT" => Ok(Base::Project),
+            other => Err(std::io::Error::new(ErrorKind::InvalidInput, format!("Base: {other} is unknown"))),
+        }
+    }
+}
+
+impl WrapperConfiguration {
+    /// The defaults, as if the properties were empty
+    pub fn new(project_dir: &Path) -> Self {
+        WrapperConfiguration {
+            distribution_url: None,
+            distribution_sha256_sum: None,
+            distribution_base: Base::GradleUserHome,
+            distribution_path: DEFAULT_DISTRIBUTION_PATH.to_string(),
+            zip_base: Base::GradleUserHome,
+            zip_path: DEFAULT_DISTRIBUTION_PATH.to_string(),
+            network_timeout: DEFAULT_NETWORK_TIMEOUT,
+            validate_distribution_url: true,
+            project_dir: project_dir.to_path_buf(),
+        }
+    }
+
+    /// Reads the wrapper properties of the project in given directory, if there are any.
+    pub fn load(project_dir: &Path) -> std::io::Result<Self> {
+        let mut config = Self::new(project_dir);
+        let properties_file = project_dir.join(WRAPPER_PROPERTIES);
+        if !properties_file.is_file() {
+            return Ok(config);
+        }
+        let mut props = HashMap::new();
+        utils::read_properties(&mut props, &properties_file)?;
+        let invalid = |key: &str, value: &str| std::io::Error::new(ErrorKind::InvalidInput,
+            format!("Invalid {key} '{value}' in {}", properties_file.display()));
+        for (key, value) in &props {
+            match key.as_str() {
+                "distributionUrl" => config.distribution_url = Some(resolve_url(value, &properties_file)),
+                "distributionSha256Sum" => config.distribution_sha256_sum = Some(value.clone()),
+                "distributionBase" => config.distribution_base = Base::parse(value)?,
+                "distributionPath" => config.distribution_path = value.clone(),
+                "zipStoreBase" => config.zip_base = Base::parse(value)?,
+                "zipStorePath" => config.zip_path = value.clone(),
+                "networkTimeout" => config.network_timeout = Duration::from_millis(value.trim().parse().map_err(|_| invalid(key, value))?),
+                // like Java's Boolean.parseBoolean
+                "validateDistributionUrl" => config.validate_distribution_url = value.trim().eq_ignore_ascii_case("true"),
+                _ => log::trace!("Ignoring {key} in {}", properties_file.display()),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Where given distribution goes; the hash is computed from the URL as written, like `PathAssembler` of the wrapper does.
+    pub fn local_distribution(&self, distribution_url: &str, gradle_user_home: &Path) -> std::io::Result<LocalDistribution> {
+        let parsed = Url::parse(distribution_url)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
+        let base_name = parsed.path().rsplit('/').next().unwrap_or_default();
+        if base_name.is_empty() {
+            return Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange distribution URL: {distribution_url}")));
+        }
+        let dist_name = base_name.rsplit_once('.').map_or(base_name, |(name, _)| name);
+        let root_dir_name = format!("{dist_name}/{}", utils::md5decimal(distribution_url));
+        let base_dir = |base: Base| match base {
+            Base::GradleUserHome => gradle_user_home,
+            Base::Project => &self.project_dir,
+        };
+        Ok(LocalDistribution {
+            dist_dir: base_dir(self.distribution_base).join(&self.distribution_path).join(&root_dir_name),
+            zip_file: base_dir(self.zip_base).join(&self.zip_path).join(&root_dir_name).join(base_name),
+        })
+    }
+
+    /// Fails early when the distribution URL does not work, instead of in the middle of a download.
+    pub fn validate(&self, url: &Url) -> std::io::Result<()> {
+        if !self.validate_distribution_url || url.scheme() == "file" {
+            return Ok(());
+        }
+        log::debug!("Validating distribution URL {}", utils::redacted(url));
+        match utils::agent(Some(self.network_timeout)).head(url.as_str()).call() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(std::io::Error::new(ErrorKind::InvalidInput, format!(
+                "Test of distribution url {} failed. Please check the values set with --gradle-distribution-url and --gradle-version. :: {}",
+                utils::redacted(url), utils::describe(&e)))),
+        }
+    }
+}
+
+impl LocalDistribution {
+    /// The unpacked distribution, if complete: like for the wrapper, it has the marker file and is the only directory in there.
+    pub fn gradle_home(&self) -> Option<PathBuf> {
+        if !self.marker_file().is_file() {
+            return None;
+        }
+        let mut dirs = std::fs::read_dir(&self.dist_dir).ok()?.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir());
+        match (dirs.next(), dirs.next()) {
+            (Some(gradle_home), None) if gradle_home.join("bin/gradle").is_file() => Some(gradle_home),
+            _ => None,
+        }
+    }
+
+    /// The wrapper marks complete installations with this file next to the archive.
+    pub fn marker_file(&self) -> PathBuf {
+        PathBuf::from(format!("{}.ok", self.zip_file.display()))
+    }
+}
+
+/// Without a scheme, the URL is a path relative to the directory of the properties file.
+/// It becomes a `file:` URL written like Java's `File.toURI()` does, because the wrapper hashes it that way.
+fn resolve_url(distribution_url: &str, properties_file: &Path) -> String {
+    match Url::parse(distribution_url) {
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let path = properties_file.parent().unwrap_or(Path::new("")).join(distribution_url);
+            let path = path.display().to_string().replace('\\', "/").replace(' ', "%20");
+            match path.starts_with('/') {
+                true => format!("file:{path}"),
+                false => format!("file:/{path}"),
+            }
+        }
+        _ => distribution_url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::utils::testing::TempDir;
+
+    use super::{LocalDistribution, WrapperConfiguration, WRAPPER_PROPERTIES};
+
+    #[test]
+    fn test_local_distribution() {
+        let url = "https://services.gradle.org/distributions/gradle-7.2-all.zip";
+        let config = WrapperConfiguration::new(Path::new("/work/project"));
+        let gradle_user_home = Path::new("/home/user/.gradle");
+        assert_eq!(LocalDistribution {
+            dist_dir: gradle_user_home.join("wrapper/dists/gradle-7.2-all/260hg96vuh6ex27h9vo47iv4d"),
+            zip_file: gradle_user_home.join("wrapper/dists/gradle-7.2-all/260hg96vuh6ex27h9vo47iv4d/gradle-7.2-all.zip"),
+        }, config.local_distribution(url, gradle_user_home).unwrap());
+    }
+
+    #[test]
+    fn test_load() {
+        let project_dir = TempDir::new("gradle-wrapper");
+        let properties_file = project_dir.join(WRAPPER_PROPERTIES);
+        std::fs::create_dir_all(properties_file.parent().unwrap()).unwrap();
+        std::fs::write(&properties_file, "distributionBase=PROJECT\ndistributionPath=.gradle/dists\n\
+            distributionUrl=../../dists/gradle-8.5-bin.zip\nnetworkTimeout=30000\nvalidateDistributionUrl=false\n").unwrap();
+        let config = WrapperConfiguration::load(&project_dir).unwrap();
+        let distribution_url = config.distribution_url.clone().unwrap();
+        assert_eq!(format!("file:{}/gradle/wrapper/../../dists/gradle-8.5-bin.zip", project_dir.display()), distribution_url);
+        assert_eq!(Duration::from_secs(30), config.network_timeout);
+        assert!(!config.validate_distribution_url);
+        let local = config.local_distribution(&distribution_url, Path::new("/home/user/.gradle")).unwrap();
+        assert!(local.dist_dir.starts_with(project_dir.join(".gradle/dists/gradle-8.5-bin")));
+        assert!(local.zip_file.starts_with("/home/user/.gradle/wrapper/dists/gradle-8.5-bin"));
+        std::fs::write(&properties_file, "zipStoreBase=SOMEWHERE\n").unwrap();
+        assert!(WrapperConfiguration::load(&project_dir).is_err());
+    }
+
+    #[test]
+    fn test_gradle_home_needs_marker() {
+        let tmp = TempDir::new("gradle-home");
+        let local = LocalDistribution {
+            dist_dir: tmp.join("dists/gradle-8.5-bin/hash"),
+            zip_file: tmp.join("dists/gradle-8.5-bin/hash/gradle-8.5-bin.zip"),
+        };
+        std::fs::create_dir_all(local.dist_dir.join("gradle-8.5/bin")).unwrap();
+        std::fs::write(local.dist_dir.join("gradle-8.5/bin/gradle"), "").unwrap();
+        // an interrupted installation
+        assert_eq!(None, local.gradle_home());
+        std::fs::write(local.marker_file(), "").unwrap();
+        assert_eq!(Some(local.dist_dir.join("gradle-8.5")), local.gradle_home());
+    }
+}
@@ -1,14 +1,23 @@
+use std::ffi::OsString;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
-use crate::{gradle, mvn};
+use crate::{gradle, mvn, utils};
+use crate::config::lock;
+use crate::javabox::cmd_env::Shell;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, bin_name = "javabox")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// never touch the network, use only installed tools; also enabled by JAVABOX_OFFLINE=1
+    #[arg(long, global = true)]
+    offline: bool,
+    /// fail unless all tools come from an up-to-date javabox.lock; also enabled by JAVABOX_LOCKED=1
+    #[arg(long, global = true)]
+    locked: bool,
 }
 
 #[derive(Subcommand)]
@@ -21,6 +30,9 @@ enum Commands {
         /// overwrite existing symlinks
         #[arg(short, long)]
         force: bool,
+        /// also create symlinks for JDK tools like `java`, `javac` or `jshell`
+        #[arg(long)]
+        jdk_tools: bool,
     },
     /// remove symlinks to javabox
     Uninstall {
@@ -33,20 +45,54 @@ enum Commands {
         #[arg(long, default_value = ".")]
         dir: PathBuf,
     },
+    /// print environment variables that put the project's tools on PATH
+    Env {
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        /// syntax of the output
+        #[arg(long, value_enum, default_value_t = Shell::Bash)]
+        shell: Shell,
+        /// print JSON instead of a shell script
+        #[arg(long, conflicts_with = "shell")]
+        json: bool,
+    },
+    /// show effective settings of the project, and where each of them comes from
+    Config {
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// write javabox.lock with the exact distributions of the project's tools
+    Lock {
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        /// resolve all tools again, instead of keeping the locked ones
+        #[arg(long)]
+        update: bool,
+    },
+    /// check for new Maven and Gradle releases now, regardless of the update check interval
+    Refresh,
+    /// install the latest Temurin JDK of given version and print its JAVA_HOME
     Adoptium {
         #[arg(short,long)]
         version: String,
     },
 }
 
-pub fn run_javabox() -> anyhow::Result<i32> {
-    let cli = Cli::parse();
+/// Runs javabox's own commands, with given arguments following the program name.
+pub fn run_javabox(args: &[OsString]) -> anyhow::Result<i32> {
+    let cli = Cli::parse_from(std::iter::once(OsString::from("javabox")).chain(args.iter().cloned()));
+    if cli.offline {
+        utils::set_offline();
+    }
+    if cli.locked {
+        lock::set_locked();
+    }
 
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     match cli.command {
-        Commands::Install { bin, force } => {
-            cmd_setup::javabox_install(javabox_bin_dir(bin)?, force)?;
+        Commands::Install { bin, force, jdk_tools } => {
+            cmd_setup::javabox_install(javabox_bin_dir(bin)?, force, jdk_tools)?;
         }
         Commands::Uninstall { bin } => {
             cmd_setup::javabox_uninstall(javabox_bin_dir(bin)?)?;
@@ -55,13 +101,29 @@ pub fn run_javabox() -> anyhow::Result<i32> {
             if dir.join("pom.xml").is_file() {
                 let config = mvn::infer_config(&dir)?;
                 config.save(&dir)?;
-            } else if dir.join("build.gradle").is_file() {
+            } else if gradle::is_gradle_project(&dir) {
                 let config = gradle::infer_config(&dir)?;
                 config.save(&dir)?;
             } else {
                 anyhow::bail!("Failed to detect java project files here, cannot infer configuration");
             }
         }
+        Commands::Env { dir, shell, json } => {
+            cmd_env::print_env(&dir, shell, json)?;
+        }
+        Commands::Config { dir } => {
+            cmd_config::print_config(&dir)?;
+        }
+        Commands::Lock { dir, update } => {
+            cmd_lock::lock(&dir, update)?;
+        }
+        Commands::Refresh => {
+            if utils::is_offline() {
+                anyhow::bail!("Offline mode: cannot refresh the lists of releases");
+            }
+            mvn::refresh_versions()?;
+            gradle::refresh_versions()?;
+        }
         Commands::Adoptium { version} => {
             cmd_adoptium::download_jdk(&version)?;
         }
@@ -88,4 +150,7 @@ fn javabox_bin_dir(bin: Option<PathBuf>) -> std::io::Result<PathBuf> {
 }
 
 mod cmd_adoptium;
+mod cmd_config;
+mod cmd_env;
+mod cmd_lock;
 mod cmd_setup;
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use crate::config::JavaboxConfig;
+use crate::config::global::{self, GlobalConfig, Setting};
+use crate::gradle::{self, is_gradle_project};
+use crate::{jdk, mvn};
+
+/// Prints the effective settings for the project in given directory, each with where it came from.
+pub(crate) fn print_config(dir: &Path) -> anyhow::Result<()> {
+    let dir = dir.canonicalize()?;
+    let config_file = global::config_file();
+    let exists = if config_file.is_file() { "" } else { " (missing)" };
+    println!("# user configuration: {}{exists}", config_file.display());
+    let dir = if dir.join("pom.xml").is_file() { mvn::find_reactor(&dir)?.0 } else { dir };
+    let config = if dir.join("pom.xml").is_file() {
+        JavaboxConfig::load_or_infer(&dir, "maven", mvn::infer_config)?
+    } else if is_gradle_project(&dir) {
+        JavaboxConfig::load_or_infer(&dir, "gradle", gradle::infer_config)?
+    } else if JavaboxConfig::is_inside(&dir) {
+        JavaboxConfig::load(&dir)?
+    } else {
+        JavaboxConfig::default()
+    };
+    let global = GlobalConfig::get()?;
+    let java = jdk::project_java_settings(&dir, config.java.as_ref(), &config.origin)?.effective()?;
+    print_setting("java.version", java.version.as_ref());
+    print_setting("java.vendor", java.vendor.as_ref());
+    let maven = config.maven.as_ref().map(|maven| Setting::new(&maven.version, &config.origin));
+    print_setting("maven.version", global.version("maven", maven, None).as_ref());
+    let gradle = config.gradle.as_ref().map(|gradle| Setting::new(&gradle.version, &config.origin));
+    print_setting("gradle.version", global.version("gradle", gradle, None).as_ref());
+    print_setting("maven.update_check_interval", Some(&global.update_check_interval("maven")?.1));
+    print_setting("gradle.update_check_interval", Some(&global.update_check_interval("gradle")?.1));
+    print_setting("hash_check", Some(&global.hash_check()?.1));
+    Ok(())
+}
+
+fn print_setting(key: &str, setting: Option<&Setting>) {
+    match setting {
+        Some(setting) => println!("{key} = \"{}\"  # {}", setting.value, setting.origin),
+        None => println!("# {key} is not set"),
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde_derive::Serialize;
+
+use crate::config::JavaboxConfig;
+use crate::config::env::ProjectEnv;
+use crate::gradle::{GradleEnv, is_gradle_project};
+use crate::jdk;
+use crate::mvn::MavenEnv;
+
+/// Syntax of the printed environment
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// Environment of the project's tooling, as the aliases would use it
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct ToolEnv {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    java_home: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maven_home: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gradle_home: Option<PathBuf>,
+    /// with the JVM options of `javabox.toml`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maven_opts: Option<String>,
+    /// arguments of `javabox.toml`, which Maven 3.9+ reads from there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maven_args: Option<String>,
+    /// what Maven gets in total, including `.mvn/maven.config` and `.mvn/jvm.config`; only informative
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maven_effective_args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maven_effective_opts: Option<String>,
+    /// `[env]` and `[<tool>.opts]` of `javabox.toml`
+    #[serde(flatten)]
+    vars: BTreeMap<String, String>,
+    /// complete new value, with the tools prepended to current `PATH`
+    path: String,
+}
+
+impl ToolEnv {
+    fn resolve(dir: &Path) -> anyhow::Result<Self> {
+        let mut env = ToolEnv::default();
+        if dir.join("pom.xml").is_file() {
+            let mut maven = MavenEnv::load_or_infer(dir)?;
+            env.vars = std::mem::take(&mut maven.env.vars);
+            env.maven_opts = maven.options.maven_opts().or(env.vars.remove("MAVEN_OPTS"));
+            let args: Vec<&str> = maven.options.args().iter().chain(&maven.env.properties).map(String::as_str).collect();
+            env.maven_args = Some(args.join(" ")).filter(|args| !args.is_empty());
+            let effective_args: Vec<&str> = maven.options.effective_args().into_iter()
+                .chain(maven.env.properties.iter().map(String::as_str))
+                .collect();
+            env.maven_effective_args = Some(effective_args.join(" "));
+            env.maven_effective_opts = Some(maven.options.effective_jvm_options());
+            env.java_home = maven.java_home;
+            env.maven_home = Some(maven.maven_home);
+        } else if is_gradle_project(dir) {
+            let gradle = GradleEnv::load_or_infer(dir)?;
+            // system properties only go to the command line
+            env.vars = gradle.env.vars;
+            env.java_home = gradle.java_home;
+            env.gradle_home = Some(gradle.gradle_home);
+        } else {
+            // no build tool, but the JDK can still be chosen by the project files
+            let config = if JavaboxConfig::is_inside(dir) { Some(JavaboxConfig::load(dir)?) } else { None };
+            let java = config.as_ref().and_then(|config| config.java.as_ref());
+            let origin = config.as_ref().map(|config| config.origin.as_str()).unwrap_or_default();
+            if let Some(java) = jdk::java_requirement(dir, java, origin)? {
+                env.java_home = Some(jdk::java_home_for(&java)?);
+            }
+            if let Some(config) = &config {
+                env.vars = ProjectEnv::resolve(config, None, dir)?.vars;
+            }
+        }
+        let mut path = env.bin_dirs();
+        if let Some(current) = std::env::var_os("PATH") {
+            path.extend(std::env::split_paths(&current));
+        }
+        env.path = std::env::join_paths(path)?.to_string_lossy().into_owned();
+        Ok(env)
+    }
+
+    /// Directories to prepend to `PATH`, in order of precedence
+    fn bin_dirs(&self) -> Vec<PathBuf> {
+        [&self.java_home, &self.maven_home, &self.gradle_home].into_iter()
+            .flatten()
+            .map(|home| home.join("bin"))
+            .collect()
+    }
+
+    fn variables(&self) -> Vec<(&str, String)> {
+        let homes = [("JAVA_HOME", &self.java_home), ("MAVEN_HOME", &self.maven_home), ("GRADLE_HOME", &self.gradle_home)].into_iter()
+            .filter_map(|(name, home)| home.as_deref().map(|home| (name, home.display().to_string())));
+        let options = [("MAVEN_OPTS", &self.maven_opts), ("MAVEN_ARGS", &self.maven_args)].into_iter()
+            .filter_map(|(name, value)| value.clone().map(|value| (name, value)));
+        let vars = self.vars.iter().map(|(name, value)| (name.as_str(), value.clone()));
+        homes.chain(options).chain(vars).collect()
+    }
+
+    fn to_script(&self, shell: Shell) -> String {
+        let mut script = String::new();
+        // the launcher adds `.mvn/*.config` itself, so these are only shown
+        if let Some(args) = self.maven_effective_args.as_deref().filter(|args| !args.is_empty()) {
+            script.push_str(&format!("# Maven arguments: {args}\n"));
+        }
+        if let Some(opts) = self.maven_effective_opts.as_deref().filter(|opts| !opts.is_empty()) {
+            script.push_str(&format!("# Maven JVM options: {opts}\n"));
+        }
+        for (name, value) in self.variables() {
+            let value = quote(shell, &value);
+            script.push_str(&match shell {
+                Shell::Bash | Shell::Zsh => format!("export {name}={value}\n"),
+                Shell::Fish => format!("set -gx {name} {value}\n"),
+                Shell::Powershell => format!("$env:{name} = {value}\n"),
+            });
+        }
+        let bin_dirs: Vec<String> = self.bin_dirs().iter().map(|dir| quote(shell, &dir.display().to_string())).collect();
+        if !bin_dirs.is_empty() {
+            script.push_str(&match shell {
+                Shell::Bash | Shell::Zsh => format!("export PATH={}:\"$PATH\"\n", bin_dirs.join(":")),
+                Shell::Fish => format!("set -gx PATH {} $PATH\n", bin_dirs.join(" ")),
+                Shell::Powershell => format!("$env:PATH = {} + [IO.Path]::PathSeparator + $env:PATH\n",
+                                             bin_dirs.join(" + [IO.Path]::PathSeparator + ")),
+            });
+        }
+        script
+    }
+}
+
+/// Single-quotes the value, so that nothing in it gets expanded
+fn quote(shell: Shell, text: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", text.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'")),
+        Shell::Powershell => format!("'{}'", text.replace('\'', "''")),
+    }
+}
+
+/// Prints the environment of the project in given directory.
+pub(crate) fn print_env(dir: &Path, shell: Shell, json: bool) -> anyhow::Result<()> {
+    let env = ToolEnv::resolve(&dir.canonicalize()?)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&env)?);
+    } else {
+        print!("{}", env.to_script(shell));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Shell, ToolEnv};
+
+    #[test]
+    fn test_to_script() {
+        let env = ToolEnv {
+            java_home: Some(PathBuf::from("/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7")),
+            maven_home: Some(PathBuf::from("/home/me/it's maven")),
+            ..Default::default()
+        };
+        assert_eq!(r#"export JAVA_HOME='/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7'
+export MAVEN_HOME='/home/me/it'\''s maven'
+export PATH='/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7/bin':'/home/me/it'\''s maven/bin':"$PATH"
+"#, env.to_script(Shell::Bash));
+        assert_eq!(r#"set -gx JAVA_HOME '/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7'
+set -gx MAVEN_HOME '/home/me/it\'s maven'
+set -gx PATH '/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7/bin' '/home/me/it\'s maven/bin' $PATH
+"#, env.to_script(Shell::Fish));
+        assert_eq!(r#"$env:JAVA_HOME = '/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7'
+$env:MAVEN_HOME = '/home/me/it''s maven'
+$env:PATH = '/home/me/.javabox/jdk/jdk-17/jdk-17.0.8+7/bin' + [IO.Path]::PathSeparator + '/home/me/it''s maven/bin' + [IO.Path]::PathSeparator + $env:PATH
+"#, env.to_script(Shell::Powershell));
+        assert_eq!("", ToolEnv::default().to_script(Shell::Zsh));
+        let env = ToolEnv {
+            maven_opts: Some("-Xmx2g -Dfile.encoding=UTF-8".to_string()),
+            maven_effective_args: Some("-T4".to_string()),
+            maven_effective_opts: Some("-XX:+UseG1GC -Xmx2g -Dfile.encoding=UTF-8".to_string()),
+            vars: [("DATA_DIR".to_string(), "/work/data".to_string())].into(),
+            ..Default::default()
+        };
+        assert_eq!(r#"# Maven arguments: -T4
+# Maven JVM options: -XX:+UseG1GC -Xmx2g -Dfile.encoding=UTF-8
+export MAVEN_OPTS='-Xmx2g -Dfile.encoding=UTF-8'
+export DATA_DIR='/work/data'
+"#, env.to_script(Shell::Bash));
+    }
+}
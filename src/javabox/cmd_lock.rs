@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use crate::config::lock::{self, JavaboxLock, LOCK_NAME};
+use crate::gradle::{self, GradleEnv};
+use crate::mvn::MavenEnv;
+
+/// Writes `javabox.lock` of the project in given directory.
+/// Locked distributions that still satisfy the configuration are kept, unless updating.
+pub(crate) fn lock(dir: &Path, update: bool) -> anyhow::Result<()> {
+    if lock::is_locked() {
+        anyhow::bail!("Locked mode does not allow changing {LOCK_NAME}");
+    }
+    let dir = dir.canonicalize()?;
+    let (project_dir, lock) = if dir.join("pom.xml").is_file() {
+        let env = MavenEnv::resolve(&dir, update)?;
+        (env.project_dir().to_path_buf(), env.lock()?)
+    } else if gradle::is_gradle_project(&dir) {
+        let env = GradleEnv::resolve(&dir, update)?;
+        (env.project_dir().to_path_buf(), env.lock()?)
+    } else {
+        anyhow::bail!("Failed to detect java project files in {}, nothing to lock", dir.display());
+    };
+    if JavaboxLock::load(&project_dir)?.as_ref() == Some(&lock) {
+        log::info!("{} is up to date", project_dir.join(LOCK_NAME).display());
+        return Ok(());
+    }
+    lock.save(&project_dir)
+}
@@ -3,6 +3,8 @@ use std::path::PathBuf;
 
 use symlink::{remove_symlink_file, symlink_file};
 
+use crate::jdk_tool::JDK_TOOLS;
+
 const ALIASES: [&str;5] = ["mvn", "mvnw", "gradle", "gradlew", "javabox"];
 
 /// Prepare javabox tools for convenient use.
@@ -14,8 +16,8 @@ const ALIASES: [&str;5] = ["mvn", "mvnw", "gradle", "gradlew", "javabox"];
 ///         - _linux_: help him by adding stuff to .bashrc
 ///         - _mac_: ?
 ///         - _windows_: ?
-/// - (re)configure symlinks/shortcuts/scripts for running each tool
-pub fn javabox_install(javabox_bin_dir: PathBuf, force_overwrite: bool) -> std::io::Result<()>{
+/// - (re)configure symlinks/shortcuts/scripts for running each tool, optionally including JDK tools like `java`
+pub fn javabox_install(javabox_bin_dir: PathBuf, force_overwrite: bool, jdk_tools: bool) -> std::io::Result<()>{
     let javabox_exe = env::current_exe()?;
     log::info!("Creating symlinks for {}", javabox_exe.display());
 
@@ -28,7 +30,8 @@ pub fn javabox_install(javabox_bin_dir: PathBuf, force_overwrite: bool) -> std::
 
     log::info!("Creating symlinks for {}", javabox_exe.display());
 
-    for alias in ALIASES {
+    let jdk_tools = if jdk_tools { JDK_TOOLS.as_slice() } else { &[] };
+    for alias in ALIASES.iter().chain(jdk_tools) {
         let symlink = javabox_bin_dir.join(alias);
         log::debug!("* {}", symlink.display());
         if symlink.exists() {
@@ -52,11 +55,14 @@ pub fn javabox_install(javabox_bin_dir: PathBuf, force_overwrite: bool) -> std::
 pub fn javabox_uninstall(javabox_bin_dir: PathBuf) -> std::io::Result<()>{
     log::info!("Removing symlinks from {}", javabox_bin_dir.display());
     let javabox_exe = env::current_exe()?;
-    for alias in ALIASES {
+    for alias in ALIASES.iter().chain(JDK_TOOLS.iter()) {
         let symlink = javabox_bin_dir.join(alias);
         log::debug!("* {}", symlink.display());
         if !symlink.exists() {
-            log::warn!("File does not exist: {}", symlink.display());
+            // JDK tools are only installed on request
+            if ALIASES.contains(alias) {
+                log::warn!("File does not exist: {}", symlink.display());
+            }
             continue;
         }
         if symlink == javabox_exe {
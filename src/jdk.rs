@@ -0,0 +1,630 @@
+//! # JDK selection
+//!
+//! Resolves the Java version required by the project to a JDK installed by javabox, installing it when missing.
+//!
+//! The requirement comes from `javabox.toml`, overridden by the `jdk.version` file in project root:
+//! ```properties
+//! VENDOR=OracleJDK
+//! MAJOR=17
+//! VERSION=17.0.8
+//! ```
+//!
+//! The version can also be a constraint on feature versions, like `>=17, <22`; see [VersionConstraint].
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use dir::home_dir;
+use url::Url;
+
+use crate::config::JavaConfig;
+use crate::config::global::{self, GlobalConfig, Setting};
+use crate::config::lock::{self, JavaboxLock, Source};
+use crate::utils;
+use crate::utils::archive::{ArchiveFormat, extract_archive};
+use crate::utils::download_distribution;
+use crate::utils::properties::Properties;
+use crate::utils::version::VersionConstraint;
+
+/// Where javabox installs JDKs, relative to user home
+const JDK_INSTALL_DIR: &str = ".javabox/jdk";
+
+/// Per-project JDK specification, see README
+const JDK_VERSION_FILE: &str = "jdk.version";
+
+/// Used when the project does not say, like when `jdk.version` specifies no version and neither does `javabox.toml`
+pub const DEFAULT_JAVA_VERSION: &str = "17";
+
+/// Distribution source of JDKs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JdkVendor {
+    /// Eclipse Temurin, downloaded from Adoptium
+    Temurin,
+    /// Oracle JDK, downloaded via Oracle's script-friendly URLs
+    Oracle,
+}
+
+impl FromStr for JdkVendor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "temurin" | "adoptium" | "eclipse" | "openjdk" | "adoptopenjdk" => Ok(JdkVendor::Temurin),
+            "oracle" | "oraclejdk" => Ok(JdkVendor::Oracle),
+            _ => anyhow::bail!("Unsupported JDK vendor: '{s}'; known are Temurin (OpenJDK) and OracleJDK"),
+        }
+    }
+}
+
+impl JdkVendor {
+    /// Decides if the `IMPLEMENTOR` from JDK's `release` file belongs to this vendor
+    fn is_implementor(&self, implementor: &str) -> bool {
+        match self {
+            JdkVendor::Temurin => implementor.contains("Adoptium") || implementor.contains("AdoptOpenJDK") || implementor.contains("Temurin"),
+            JdkVendor::Oracle => implementor.starts_with("Oracle"),
+        }
+    }
+}
+
+/// Parsed form of [JavaConfig]
+#[derive(Debug, PartialEq)]
+struct JdkRequirement {
+    vendor: Option<JdkVendor>,
+    major: u32,
+    /// set when the configured version is more specific than just the major, like `17.0.8` or `1.8.0_341`
+    exact: Option<Vec<u32>>,
+    /// set for constraints like `>=17, <22`; the major is then resolved from what is installed or available
+    constraint: Option<VersionConstraint>,
+}
+
+impl JdkRequirement {
+    fn parse(java: &JavaConfig) -> anyhow::Result<Self> {
+        let vendor = match &java.vendor {
+            None => None,
+            Some(vendor) => Some(vendor.parse()?),
+        };
+        if VersionConstraint::is_constraint(&java.version) {
+            let constraint = VersionConstraint::parse(&java.version)?;
+            return Ok(JdkRequirement { vendor, major: 0, exact: None, constraint: Some(constraint) });
+        }
+        let major = java_major(&java.version)
+            .ok_or(anyhow::anyhow!("Unsupported java version: '{}'", java.version))?;
+        let key = version_key(&java.version);
+        Ok(JdkRequirement {
+            vendor,
+            major,
+            exact: if key.len() > 1 { Some(key) } else { None },
+            constraint: None,
+        })
+    }
+
+    fn matches(&self, jdk: &InstalledJdk) -> bool {
+        if let Some(vendor) = self.vendor {
+            match &jdk.implementor {
+                Some(implementor) if vendor.is_implementor(implementor) => {}
+                _ => return false,
+            }
+        }
+        self.matches_version(&jdk.version)
+    }
+
+    fn matches_version(&self, version: &str) -> bool {
+        let key = version_key(version);
+        match (&self.constraint, &self.exact) {
+            (Some(constraint), _) => constraint.matches(&normalized_version(version)),
+            (None, None) => key.first() == Some(&self.major),
+            (None, Some(exact)) => key.starts_with(exact),
+        }
+    }
+
+    /// Turns a constraint into the requirement of the highest feature version satisfying it, that is available for installation.
+    fn resolve_available(&self, constraint: &VersionConstraint, installed: &[InstalledJdk]) -> anyhow::Result<Self> {
+        let available: Vec<String> = adoptium::AdoptiumApi::new().available_releases()?.iter()
+            .map(u32::to_string)
+            .collect();
+        match constraint.best(available.iter().map(String::as_str)).and_then(java_major) {
+            Some(major) => Ok(JdkRequirement { vendor: self.vendor, major, exact: None, constraint: None }),
+            None => {
+                let mut candidates: Vec<String> = installed.iter().map(|jdk| normalized_version(&jdk.version)).collect();
+                candidates.extend(available);
+                Err(constraint.unsatisfied("JDK", &candidates).into())
+            }
+        }
+    }
+}
+
+/// A JDK found in the javabox installation directory
+#[derive(Debug)]
+pub struct InstalledJdk {
+    pub java_home: PathBuf,
+    /// value of `JAVA_VERSION` from the `release` file, like `17.0.8.1` or `1.8.0_382`
+    pub version: String,
+    /// value of `IMPLEMENTOR` from the `release` file, like `Eclipse Adoptium`
+    pub implementor: Option<String>,
+}
+
+/// Java version and vendor, each with where it came from
+#[derive(Debug, Default, Clone)]
+pub struct JavaSettings {
+    pub version: Option<Setting>,
+    pub vendor: Option<Setting>,
+}
+
+impl JavaSettings {
+    /// Layers environment and user configuration over these project settings, see [crate::config::global].
+    pub fn effective(self) -> anyhow::Result<Self> {
+        let global = GlobalConfig::get()?;
+        let vendor = global.java_vendor(self.vendor);
+        let jdk_vendor = match &vendor {
+            Some(vendor) => vendor.value.parse()?,
+            None => JdkVendor::Temurin,
+        };
+        let default_major = global.java_default_major(|name| name.parse::<JdkVendor>().is_ok_and(|v| v == jdk_vendor));
+        let version = global.version("java", self.version, default_major);
+        Ok(JavaSettings { version, vendor })
+    }
+
+    pub fn to_config(&self) -> Option<JavaConfig> {
+        Some(JavaConfig {
+            version: self.version.as_ref()?.value.clone(),
+            vendor: self.vendor.as_ref().map(|vendor| vendor.value.clone()),
+        })
+    }
+}
+
+/// Determines the project's own Java settings; `origin` tells where the `java` configuration comes from.
+/// Values from `jdk.version` file take precedence over `java` section of `javabox.toml`.
+pub fn project_java_settings(project_dir: &Path, java: Option<&JavaConfig>, origin: &str) -> anyhow::Result<JavaSettings> {
+    let configured = JavaSettings {
+        version: java.map(|java| Setting::new(&java.version, origin)),
+        vendor: java.and_then(|java| java.vendor.as_ref()).map(|vendor| Setting::new(vendor, origin)),
+    };
+    let jdk_version_file = project_dir.join(JDK_VERSION_FILE);
+    if !jdk_version_file.is_file() {
+        return Ok(configured);
+    }
+    let mut props = HashMap::new();
+    utils::read_properties(&mut props, &jdk_version_file)?;
+    let file_origin = jdk_version_file.display().to_string();
+    let version = props.get("VERSION")
+        .or(props.get("MAJOR"))
+        .map(|version| Setting::new(version, &file_origin))
+        .or(configured.version)
+        .unwrap_or(Setting::new(DEFAULT_JAVA_VERSION, "built-in default"));
+    let vendor = props.get("VENDOR")
+        .map(|vendor| Setting::new(vendor, &file_origin))
+        .or(configured.vendor);
+    log::debug!("{}: version={} vendor={vendor:?}", jdk_version_file.display(), version.value);
+    Ok(JavaSettings { version: Some(version), vendor })
+}
+
+/// Determines the project's effective Java requirement, layered with user configuration and environment.
+pub fn java_requirement(project_dir: &Path, java: Option<&JavaConfig>, origin: &str) -> anyhow::Result<Option<JavaConfig>> {
+    Ok(project_java_settings(project_dir, java, origin)?.effective()?.to_config())
+}
+
+/// Finds the JDK for given project configuration; the newest installed one matching the requirement is preferred.
+/// If there is none, the matching release (or the latest of the major version) gets installed.
+pub fn java_home_for(java: &JavaConfig) -> anyhow::Result<PathBuf> {
+    let requirement = JdkRequirement::parse(java)?;
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    let installed = installed_jdks(&user_home);
+    let best = installed.iter()
+        .filter(|jdk| requirement.matches(jdk))
+        .max_by_key(|jdk| version_key(&jdk.version));
+    match (best, &requirement.constraint) {
+        (Some(jdk), _) => {
+            if let Some(java_home) = newer_patch(&user_home, &requirement, jdk) {
+                return Ok(java_home);
+            }
+            log::debug!("Using installed JDK {} for java version '{}': {}", jdk.version, java.version, jdk.java_home.display());
+            Ok(jdk.java_home.clone())
+        }
+        (None, Some(constraint)) if utils::is_offline() => {
+            let candidates: Vec<String> = installed.iter().map(|jdk| normalized_version(&jdk.version)).collect();
+            Err(constraint.unsatisfied("installed JDK", &candidates).into())
+        }
+        (None, Some(constraint)) => {
+            let requirement = requirement.resolve_available(constraint, &installed)?;
+            log::info!("No installed JDK satisfies java version '{}', installing JDK {}", java.version, requirement.major);
+            install_required_jdk(&user_home, &java.version, &requirement)
+        }
+        (None, None) if utils::is_offline() => {
+            let vendor = java.vendor.as_ref().map(|vendor| format!(" from {vendor}")).unwrap_or_default();
+            anyhow::bail!("Offline mode: no installed JDK matches java version '{}'{vendor}, and it cannot be installed", java.version)
+        }
+        (None, None) => {
+            log::info!("No installed JDK for java version '{}', installing it", java.version);
+            install_required_jdk(&user_home, &java.version, &requirement)
+        }
+    }
+}
+
+/// For a major-only requirement like `17`, installs the newest Temurin release of that feature version
+/// when it is newer than the installed JDK. A failed check keeps the installed JDK.
+fn newer_patch(user_home: &Path, requirement: &JdkRequirement, installed: &InstalledJdk) -> Option<PathBuf> {
+    // Oracle's `latest` archive does not tell its version, so any installed patch is reused
+    if utils::is_offline() || requirement.exact.is_some() || requirement.constraint.is_some()
+        || requirement.vendor.unwrap_or(JdkVendor::Temurin) != JdkVendor::Temurin {
+        return None;
+    }
+    let (release_name, url) = match latest_release(user_home, requirement.major) {
+        Ok(latest) => latest,
+        Err(e) => {
+            log::warn!("Cannot check for a newer JDK {}, using installed {} :: {e}", requirement.major, installed.version);
+            return None;
+        }
+    };
+    if version_key(&release_name) <= version_key(&installed.version) {
+        return None;
+    }
+    log::info!("Installing JDK {release_name}, newer than installed {}", installed.version);
+    match get_jdk_home(user_home, &url, &release_name, None) {
+        Ok(java_home) => Some(java_home),
+        Err(e) => {
+            log::warn!("Failed to install JDK {release_name}, using installed {} :: {e}", installed.version);
+            None
+        }
+    }
+}
+
+/// The newest Temurin release of given feature version, and its binary URL.
+/// The answer is kept in `~/.javabox/jdk/temurin-{major}.latest`, and asked again once the update check interval elapses.
+fn latest_release(user_home: &Path, major: u32) -> anyhow::Result<(String, Url)> {
+    let latest_file = user_home.join(JDK_INSTALL_DIR).join(format!("temurin-{major}.latest"));
+    let max_age = global::update_check_interval("java")?;
+    let fresh = std::fs::metadata(&latest_file).and_then(|stat| stat.modified()).ok()
+        .and_then(|time| time.elapsed().ok())
+        .is_some_and(|age| age <= max_age);
+    if fresh {
+        let text = std::fs::read_to_string(&latest_file)?;
+        if let Some((release_name, url)) = text.trim().split_once('\n') {
+            if let Ok(url) = url.parse() {
+                return Ok((release_name.to_string(), url));
+            }
+        }
+        log::debug!("Ignoring malformed {}", latest_file.display());
+    }
+    let url = adoptium::AdoptiumApi::new().info_binary_latest(&major.to_string())?;
+    let release_name = release_name_from_url(&url)?;
+    std::fs::create_dir_all(user_home.join(JDK_INSTALL_DIR))?;
+    std::fs::write(&latest_file, format!("{release_name}\n{url}\n"))?;
+    Ok((release_name, url))
+}
+
+/// Like [java_home_for], but the JDK locked in `javabox.lock` wins while it satisfies the requirement.
+/// Also tells where the JDK comes from, for locking it.
+pub fn resolve_java(java: &JavaConfig, lock: Option<&JavaboxLock>) -> anyhow::Result<(PathBuf, Source)> {
+    let requirement = JdkRequirement::parse(java)?;
+    match lock::locked(lock, "java", |version| requirement.matches_version(version))? {
+        Some(locked) => {
+            let user_home = home_dir().expect("There is no HOME directory?!");
+            let url: Url = locked.url.parse()?;
+            let release_name = locked.release.as_deref().unwrap_or(&locked.version);
+            let java_home = get_jdk_home(&user_home, &url, release_name, Some(&locked.sha256))?;
+            let jdk_base = jdk_dir(&java_home).parent().unwrap_or(&java_home).to_path_buf();
+            Ok((java_home, Source::from_locked(locked, &jdk_base)))
+        }
+        None => {
+            let java_home = java_home_for(java)?;
+            let source = jdk_source(&java_home);
+            Ok((java_home, source))
+        }
+    }
+}
+
+/// Where an installed JDK was downloaded from; recorded since javabox started writing `{release_name}.url` files
+fn jdk_source(java_home: &Path) -> Source {
+    let jdk_dir = jdk_dir(java_home);
+    let release_name = jdk_dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let version = Properties::load(&java_home.join("release")).ok()
+        .and_then(|release| release.get("JAVA_VERSION").map(|v| v.trim_matches('"').to_string()))
+        .unwrap_or(release_name.clone());
+    let url = std::fs::read_to_string(jdk_dir.with_file_name(format!("{release_name}.url")));
+    let mut source = match url {
+        Ok(url) => Source::new(&version, url.trim(), jdk_dir.parent().unwrap_or(jdk_dir), None),
+        Err(_) => Source { version, release: None, url: None, archive: jdk_dir.to_path_buf(), sha256: None },
+    };
+    source.release = Some(release_name);
+    source
+}
+
+/// The installation directory; on macOS, JAVA_HOME is inside it
+fn jdk_dir(java_home: &Path) -> &Path {
+    match java_home.ends_with("Contents/Home") {
+        true => java_home.parent().and_then(Path::parent).unwrap_or(java_home),
+        false => java_home,
+    }
+}
+
+/// Version of the newest JDK installed by javabox, of given vendor if any
+pub fn newest_installed(user_home: &Path, vendor: Option<&str>) -> anyhow::Result<Option<String>> {
+    let vendor = vendor.map(str::parse::<JdkVendor>).transpose()?;
+    Ok(installed_jdks(user_home).into_iter()
+        .filter(|jdk| match vendor {
+            None => true,
+            Some(vendor) => jdk.implementor.as_deref().is_some_and(|implementor| vendor.is_implementor(implementor)),
+        })
+        .max_by_key(|jdk| version_key(&jdk.version))
+        .map(|jdk| jdk.version))
+}
+
+/// Lists JDKs installed by javabox.
+pub fn installed_jdks(user_home: &Path) -> Vec<InstalledJdk> {
+    let mut jdks = Vec::new();
+    let bases = match std::fs::read_dir(user_home.join(JDK_INSTALL_DIR)) {
+        Ok(bases) => bases,
+        Err(_) => return jdks,
+    };
+    for base in bases.flatten() {
+        let releases = match std::fs::read_dir(base.path()) {
+            Ok(releases) => releases,
+            Err(_) => continue,
+        };
+        for release in releases.flatten() {
+            let jdk_dir = release.path();
+            if !jdk_dir.is_dir() || jdk_dir.extension().is_some_and(|ext| ext == "tmp") {
+                continue;
+            }
+            let java_home = match jdk_dir.join("Contents/Home") {
+                mac_home if mac_home.is_dir() => mac_home,
+                _ => jdk_dir,
+            };
+            let release = match Properties::load(&java_home.join("release")) {
+                Ok(release) => release,
+                Err(_) => {
+                    log::warn!("Not a JDK, ignoring: {}", java_home.display());
+                    continue;
+                }
+            };
+            let value = |key: &str| release.get(key).map(|v| v.trim_matches('"').to_string());
+            match value("JAVA_VERSION") {
+                Some(version) => jdks.push(InstalledJdk { java_home, version, implementor: value("IMPLEMENTOR") }),
+                None => log::warn!("No JAVA_VERSION in release file, ignoring: {}", java_home.display()),
+            }
+        }
+    }
+    jdks
+}
+
+/// Extracts the feature (major) version: `1.8`, `1.8.0_382` and `8u382-b05` all give `8`, `17.0.8.1` gives `17`.
+pub fn java_major(version: &str) -> Option<u32> {
+    version_key(version).first().copied()
+}
+
+/// Numeric components of a version, normalized for comparison:
+/// legacy `1.` prefix is dropped, and `8u382` is the same as `1.8.0_382`, ie. `8.0.382`.
+/// Build numbers (`+7`, `-b05`) are ignored.
+fn version_key(version: &str) -> Vec<u32> {
+    let version = version.trim().trim_start_matches("jdk-").trim_start_matches("jdk");
+    let version = version.split(['+', '-']).next().unwrap_or_default();
+    let version = version.replacen('u', ".0.", 1).replace('_', ".");
+    let key: Vec<u32> = version.split('.')
+        .take_while(|s| !s.is_empty())
+        .map_while(|s| s.parse().ok())
+        .collect();
+    match key.as_slice() {
+        [1, rest @ ..] if !rest.is_empty() => rest.to_vec(),
+        _ => key,
+    }
+}
+
+/// The version in the form used by constraints, like `8.0.382` for `1.8.0_382`
+fn normalized_version(version: &str) -> String {
+    let key: Vec<String> = version_key(version).iter().map(u32::to_string).collect();
+    key.join(".")
+}
+
+/// Makes the tool use given JDK: sets `JAVA_HOME` and puts its `bin` first on `PATH`.
+pub fn apply_java_home(command: &mut Command, java_home: &Path) -> std::io::Result<()> {
+    let mut path = vec![java_home.join("bin")];
+    if let Some(current) = std::env::var_os("PATH") {
+        path.extend(std::env::split_paths(&current));
+    }
+    let path: OsString = std::env::join_paths(path)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Cannot put {} on PATH :: {e:?}", java_home.display())))?;
+    log::debug!("JAVA_HOME={}", java_home.display());
+    command.env("JAVA_HOME", java_home);
+    command.env("PATH", path);
+    Ok(())
+}
+
+/// Makes sure that the latest Temurin JDK of given feature version is installed, and returns its JAVA_HOME.
+/// The installation is keyed by the exact release, so repeated calls reuse it without downloading anything.
+pub(crate) fn install_jdk(version: &str) -> anyhow::Result<PathBuf> {
+    if utils::is_offline() {
+        anyhow::bail!("Offline mode: cannot look up the latest JDK {version}");
+    }
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    let adoptium = adoptium::AdoptiumApi::new();
+    let location = adoptium.info_binary_latest(version)?;
+    let release_name = release_name_from_url(&location)?;
+    let java_home = get_jdk_home(&user_home, &location, &release_name, None)?;
+    log::debug!("java_home={}", java_home.display());
+    Ok(java_home)
+}
+
+fn install_required_jdk(user_home: &Path, version: &str, requirement: &JdkRequirement) -> anyhow::Result<PathBuf> {
+    let java_home = match (requirement.vendor.unwrap_or(JdkVendor::Temurin), &requirement.exact) {
+        (JdkVendor::Temurin, None) => install_jdk(&requirement.major.to_string())?,
+        (JdkVendor::Temurin, Some(exact)) => {
+            let exact: Vec<String> = exact.iter().map(|n| n.to_string()).collect();
+            let binary = adoptium::AdoptiumApi::new().info_binary_version(&exact.join("."))?;
+            get_jdk_home(user_home, &binary.link, &binary.release_name, binary.checksum.as_deref())?
+        }
+        (JdkVendor::Oracle, exact) => {
+            // Oracle uses plain version in its file names, like `17.0.8`
+            let exact = exact.as_ref().map(|_| version.trim_start_matches("jdk-"));
+            let url = oracle_binary_url(requirement.major, exact)?;
+            let release_name = match exact {
+                None => "latest".to_string(),
+                Some(exact) => format!("jdk-{exact}"),
+            };
+            get_jdk_home(user_home, &url, &release_name, None)?
+        }
+    };
+    log::debug!("java_home={}", java_home.display());
+    Ok(java_home)
+}
+
+/// Oracle JDK script-friendly URLs, see `doc/jdk-auto-get.md`
+fn oracle_binary_url(major: u32, exact: Option<&str>) -> anyhow::Result<Url> {
+    let os = if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        std::env::consts::OS
+    };
+    let arch = if cfg!(target_arch = "x86_64") {
+        "x64"
+    } else {
+        std::env::consts::ARCH
+    };
+    let ext = if cfg!(windows) { "zip" } else { "tar.gz" };
+    let url = match exact {
+        None => format!("https://download.oracle.com/java/{major}/latest/jdk-{major}_{os}-{arch}_bin.{ext}"),
+        Some(exact) => format!("https://download.oracle.com/java/{major}/archive/jdk-{exact}_{os}-{arch}_bin.{ext}"),
+    };
+    Ok(url.parse()?)
+}
+
+/// Adoptium binary URLs have the release name as the parent path segment, like `.../jdk-17.0.8.1%2B1/OpenJDK17U-jdk_x64_linux_hotspot_17.0.8.1_1.tar.gz`
+fn release_name_from_url(binary_url: &Url) -> std::io::Result<String> {
+    let segments: Vec<&str> = binary_url.path_segments()
+        .map(|s| s.collect())
+        .unwrap_or_default();
+    match segments.as_slice() {
+        [.., release_name, _] => Ok(release_name.replace("%2B", "+")),
+        _ => Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange JDK binary URL: {binary_url}")))
+    }
+}
+
+/// Determines JDK installation directory for given binary URL; downloads and extracts it if not there yet.
+///
+/// Layout: `~/.javabox/jdk/{archive_base_name}/{release_name}`, with the downloaded archive and `{release_name}.url` next to the extracted JDK.
+fn get_jdk_home(user_home: &Path, binary_url: &Url, release_name: &str, checksum: Option<&str>) -> std::io::Result<PathBuf> {
+    let archive_name = match binary_url.path_segments().and_then(|mut s| s.next_back()) {
+        Some(archive_name) if !archive_name.is_empty() => archive_name,
+        _ => return Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange JDK binary URL: {binary_url}")))
+    };
+    let base_name = ArchiveFormat::base_name(archive_name);
+    let jdk_base = user_home.join(JDK_INSTALL_DIR).join(base_name);
+    let jdk_dir = jdk_base.join(release_name);
+    if !jdk_dir.is_dir() {
+        let archive_path = jdk_base.join(archive_name);
+        if !archive_path.is_file() {
+            std::fs::create_dir_all(&jdk_base)?;
+            download_distribution(binary_url, &archive_path, checksum, None)?;
+        }
+        // extract next to the final location first, so that an interrupted extraction is never mistaken for an installed JDK
+        let tmp_dir = jdk_base.join(format!("{release_name}.tmp"));
+        if tmp_dir.exists() {
+            std::fs::remove_dir_all(&tmp_dir)?;
+        }
+        log::info!("Extracting {} to {}", archive_path.display(), jdk_dir.display());
+        extract_archive(&archive_path, &tmp_dir)?;
+        std::fs::rename(&tmp_dir, &jdk_dir)?;
+        // the source is needed for locking the JDK later
+        std::fs::write(jdk_base.join(format!("{release_name}.url")), binary_url.as_str())?;
+    }
+    // macOS bundles have the actual JDK inside
+    let mac_home = jdk_dir.join("Contents/Home");
+    if mac_home.is_dir() {
+        return Ok(mac_home);
+    }
+    Ok(jdk_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::config::JavaConfig;
+    use crate::utils::testing::TempDir;
+
+    use super::{InstalledJdk, JdkRequirement, JdkVendor, JDK_INSTALL_DIR, java_major, latest_release, newest_installed, version_key};
+
+    fn installed(version: &str, implementor: &str) -> InstalledJdk {
+        InstalledJdk {
+            java_home: PathBuf::from("/nowhere"),
+            version: version.to_string(),
+            implementor: Some(implementor.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_java_major() {
+        assert_eq!(Some(8), java_major("1.8"));
+        assert_eq!(Some(8), java_major("8"));
+        assert_eq!(Some(8), java_major("1.8.0_382"));
+        assert_eq!(Some(8), java_major("8u382-b05"));
+        assert_eq!(Some(11), java_major("11"));
+        assert_eq!(Some(17), java_major("17.0.8.1"));
+        assert_eq!(Some(21), java_major("jdk-21+35"));
+        assert_eq!(None, java_major("latest"));
+    }
+
+    #[test]
+    fn test_version_key() {
+        assert_eq!(vec![8, 0, 382], version_key("1.8.0_382"));
+        assert_eq!(vec![8, 0, 382], version_key("8u382-b05"));
+        assert_eq!(vec![17, 0, 8, 1], version_key("jdk-17.0.8.1+1"));
+        assert!(version_key("17.0.10") > version_key("17.0.9"));
+        // release names against `JAVA_VERSION` of installed JDKs
+        assert!(version_key("jdk-17.0.10+7") > version_key("17.0.9"));
+        assert_eq!(version_key("jdk8u392-b08"), version_key("1.8.0_392"));
+    }
+
+    #[test]
+    fn test_latest_release_reused() {
+        let user_home = TempDir::new("jdk-latest");
+        let jdk_dir = user_home.join(JDK_INSTALL_DIR);
+        std::fs::create_dir_all(&jdk_dir).unwrap();
+        let url = "https://github.com/adoptium/temurin17-binaries/releases/download/jdk-17.0.10%2B7/OpenJDK17U-jdk_x64_linux_hotspot_17.0.10_7.tar.gz";
+        std::fs::write(jdk_dir.join("temurin-17.latest"), format!("jdk-17.0.10+7\n{url}\n")).unwrap();
+        // fresh, so there is no request
+        let (release_name, latest_url) = latest_release(&user_home, 17).unwrap();
+        assert_eq!("jdk-17.0.10+7", release_name);
+        assert_eq!(url, latest_url.as_str());
+    }
+
+    #[test]
+    fn test_newest_installed() {
+        let user_home = TempDir::new("jdk-newest");
+        assert_eq!(None, newest_installed(&user_home, None).unwrap());
+        for (dir, version, implementor) in [("temurin/jdk-17.0.1", "17.0.1", "Eclipse Adoptium"), ("temurin/jdk-11.0.2", "11.0.2", "Eclipse Adoptium"),
+                                            ("oracle/jdk-21", "21", "Oracle Corporation")] {
+            let java_home = user_home.join(JDK_INSTALL_DIR).join(dir);
+            std::fs::create_dir_all(&java_home).unwrap();
+            std::fs::write(java_home.join("release"), format!("JAVA_VERSION=\"{version}\"\nIMPLEMENTOR=\"{implementor}\"\n")).unwrap();
+        }
+        assert_eq!(Some("21".to_string()), newest_installed(&user_home, None).unwrap());
+        assert_eq!(Some("17.0.1".to_string()), newest_installed(&user_home, Some("temurin")).unwrap());
+    }
+
+    #[test]
+    fn test_requirement() {
+        let java = JavaConfig { version: "1.8".to_string(), vendor: None };
+        let requirement = JdkRequirement::parse(&java).unwrap();
+        assert_eq!(JdkRequirement { vendor: None, major: 8, exact: None, constraint: None }, requirement);
+        assert!(requirement.matches(&installed("1.8.0_382", "Eclipse Adoptium")));
+        assert!(!requirement.matches(&installed("17.0.8", "Eclipse Adoptium")));
+
+        let java = JavaConfig { version: "17.0.8".to_string(), vendor: Some("OracleJDK".to_string()) };
+        let requirement = JdkRequirement::parse(&java).unwrap();
+        assert_eq!(Some(JdkVendor::Oracle), requirement.vendor);
+        assert!(requirement.matches(&installed("17.0.8", "Oracle Corporation")));
+        assert!(!requirement.matches(&installed("17.0.8", "Eclipse Adoptium")));
+        assert!(!requirement.matches(&installed("17.0.9", "Oracle Corporation")));
+
+        let java = JavaConfig { version: ">=11, <17".to_string(), vendor: None };
+        let requirement = JdkRequirement::parse(&java).unwrap();
+        assert!(requirement.matches(&installed("11.0.22", "Eclipse Adoptium")));
+        assert!(!requirement.matches(&installed("1.8.0_382", "Eclipse Adoptium")));
+        assert!(!requirement.matches(&installed("17.0.8", "Eclipse Adoptium")));
+    }
+}
+
+mod adoptium;
@@ -0,0 +1,228 @@
+use serde_derive::Deserialize;
+use url::Url;
+
+// implementation of Adoptium REST API
+// https://api.adoptium.net/q/swagger-ui/
+
+pub(crate) struct AdoptiumApi {
+    client: ureq::Agent,
+}
+
+const ADOPTIUM_OS: &str = if cfg!(target_os = "macos") {
+    "mac"
+} else {
+    std::env::consts::OS
+};
+
+const ADOPTIUM_ARCH: &str = if cfg!(target_arch = "x86_64") {
+    "x64"
+} else if cfg!(target_arch = "powerpc") {
+    "ppc"
+} else if cfg!(target_arch = "powerpc64") {
+    "ppc64"
+} else {
+    std::env::consts::ARCH
+};
+
+impl AdoptiumApi {
+    pub fn new() -> Self {
+        Self {
+            client: ureq::AgentBuilder::new()
+                .timeout_read(std::time::Duration::from_secs(30))
+                .timeout_write(std::time::Duration::from_secs(30))
+                .redirects(0)
+                .build(),
+        }
+    }
+
+    pub fn info_binary_latest(&self, version: &str) -> anyhow::Result<Url> {
+        let image_type = "jdk";
+        let jvm_impl = "hotspot";
+        let heap_size = "normal";
+        let vendor = "eclipse";
+        let os = ADOPTIUM_OS;
+        let arch = ADOPTIUM_ARCH;
+        self.get_info_binary_latest(version, os, arch, image_type, jvm_impl, heap_size, vendor)
+    }
+
+    /// Finds the JDK binary of an exact release, like `17.0.8` or `8.0.382`.
+    pub fn info_binary_version(&self, version: &str) -> anyhow::Result<AdoptiumBinary> {
+        self.get_assets_version(version, ADOPTIUM_OS, ADOPTIUM_ARCH, "jdk", "hotspot", "normal", "eclipse")
+    }
+
+    /// GET /v3/info/available_releases
+    /// Feature versions that have GA releases, like `[8, 11, 17, 21, 22]`.
+    pub fn available_releases(&self) -> anyhow::Result<Vec<u32>> {
+        let url = "https://api.adoptium.net/v3/info/available_releases";
+        log::info!("GET {url}");
+        let response = self.client.get(url)
+            .call()?;
+        let releases: AvailableReleases = serde_json::from_reader(response.into_reader())?;
+        Ok(releases.available_releases)
+    }
+
+    /// GET /v3/binary/version/{release_name}/{os}/{arch}/{image_type}/{jvm_impl}/{heap_size}/{vendor}
+    /// Redirects to the binary that matches your current query.
+    /// Matching CURL example:
+    /// ```shell
+    /// curl -v 'https://api.adoptium.net/v3/binary/latest/17/ga/mac/x64/jdk/hotspot/normal/eclipse'
+    /// ```
+    ///
+    /// Params:
+    /// - `feature_version`: The version of the JDK you want to download. This can be a major version (e.g. `8`) or a release name (e.g. `8u212-b03`).
+    /// - `os`: The operating system you want to download. This can be:
+    ///   * `linux`
+    ///   * `mac`
+    ///   * `windows`
+    ///   * `aix`
+    ///   * `solaris`
+    /// - `arch`: The architecture you want to download. This can be:
+    ///   * `x64`
+    ///   * `x32`
+    ///   * `x86`
+    ///   * `ppc64`
+    ///   * `ppc64le`
+    ///   * `s390x`
+    ///   * `aarch64`
+    ///   * `arm`
+    ///   * `sparcv9`
+    ///   * `riscv64`
+    /// - `image_type`: The type of image you want to download. This can be:
+    ///   * `jdk`
+    ///   * `jre`
+    ///   * `testimage`
+    ///   * `debugimage`
+    ///   * `staticlibs`
+    ///   * `source`
+    ///   * `sbom`
+    /// - `jvm_impl`: The JVM implementation you want to download. This can be:
+    ///   * `hotspot`
+    ///   * `openj9`
+    /// - `heap_size`: The heap size you want to download. This can be:
+    ///   * `normal`
+    ///   * `large`
+    /// - `vendor`: The vendor you want to download. This can be:
+    ///   * `eclipse`
+    ///
+    #[allow(clippy::too_many_arguments)]
+    fn get_info_binary_latest(&self, feature_version: &str, os: &str, arch: &str, image_type: &str, jvm_impl: &str, heap_size: &str, vendor: &str) -> anyhow::Result<Url> {
+        let release_type = "ga";
+        let url = format!("https://api.adoptium.net/v3/binary/latest/{}/{release_type}/{}/{}/{}/{}/{}/{}", feature_version, os, arch, image_type, jvm_impl, heap_size, vendor);
+        log::info!("GET {url}");
+        let response = self.client.get(&url)
+            .call()?;
+        if response.status() != 307 {
+            anyhow::bail!("Expected 307, got {}", response.status());
+        }
+        // read the Location header
+        let location = response.header("Location")
+            .ok_or(anyhow::anyhow!("No Location header in response from {url}"))?;
+        log::info!("Location: {}", location);
+        let location: Url = location.parse()?;
+        let file_name = location
+            .path_segments().ok_or(anyhow::anyhow!("No path segments in URL"))?
+            .next_back().ok_or(anyhow::anyhow!("No file name in URL"))?;
+        log::info!("file_name: {}", file_name);
+        Ok(location.to_owned())
+    }
+
+    /// GET /v3/assets/version/{version}
+    /// Lists releases within a version range (maven style), newest first.
+    /// Matching CURL example:
+    /// ```shell
+    /// curl 'https://api.adoptium.net/v3/assets/version/%5B17.0.8%2C17.0.9%29?os=linux&architecture=x64&image_type=jdk&jvm_impl=hotspot&heap_size=normal&vendor=eclipse&release_type=ga&page_size=1&sort_order=DESC'
+    /// ```
+    ///
+    /// The version is turned into range `[version,next)` where `next` increments its last component,
+    /// so that `17.0.8` matches `17.0.8+7` but not `17.0.9`.
+    #[allow(clippy::too_many_arguments)]
+    fn get_assets_version(&self, version: &str, os: &str, arch: &str, image_type: &str, jvm_impl: &str, heap_size: &str, vendor: &str) -> anyhow::Result<AdoptiumBinary> {
+        let range = exact_version_range(version)
+            .ok_or(anyhow::anyhow!("Unsupported JDK version: '{version}'"))?;
+        let mut url: Url = "https://api.adoptium.net/v3/assets/version".parse()?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Cannot build Adoptium URL"))?
+            .push(&range);
+        url.query_pairs_mut()
+            .append_pair("os", os)
+            .append_pair("architecture", arch)
+            .append_pair("image_type", image_type)
+            .append_pair("jvm_impl", jvm_impl)
+            .append_pair("heap_size", heap_size)
+            .append_pair("vendor", vendor)
+            .append_pair("release_type", "ga")
+            .append_pair("page_size", "1")
+            .append_pair("sort_order", "DESC");
+        log::info!("GET {url}");
+        let response = self.client.get(url.as_str())
+            .call()?;
+        let releases: Vec<AssetsRelease> = serde_json::from_reader(response.into_reader())?;
+        let release = releases.into_iter().next()
+            .ok_or(anyhow::anyhow!("No Adoptium release matches JDK version {version}"))?;
+        let binary = release.binaries.into_iter().next()
+            .ok_or(anyhow::anyhow!("No Adoptium binary for {} on {os}/{arch}", release.release_name))?;
+        log::info!("Release {}: {}", release.release_name, binary.package.link);
+        Ok(AdoptiumBinary {
+            link: binary.package.link.parse()?,
+            release_name: release.release_name,
+            checksum: binary.package.checksum,
+        })
+    }
+}
+
+/// Selected JDK binary
+pub(crate) struct AdoptiumBinary {
+    pub link: Url,
+    /// like `jdk-17.0.8+7` or `jdk8u382-b05`
+    pub release_name: String,
+    /// SHA-256 of the package
+    pub checksum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AvailableReleases {
+    available_releases: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetsRelease {
+    release_name: String,
+    binaries: Vec<AssetsBinary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetsBinary {
+    package: AssetsPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetsPackage {
+    link: String,
+    checksum: Option<String>,
+}
+
+/// Builds maven-style range `[version,next)`, where `next` has the last numeric component incremented.
+fn exact_version_range(version: &str) -> Option<String> {
+    // build number is not part of the range
+    let version = version.split('+').next()?;
+    let mut parts: Vec<u32> = version.split('.')
+        .map(|p| p.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let last = parts.last_mut()?;
+    *last += 1;
+    let next: Vec<String> = parts.iter().map(|p| p.to_string()).collect();
+    Some(format!("[{version},{})", next.join(".")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::exact_version_range;
+
+    #[test]
+    fn test_exact_version_range() {
+        assert_eq!(Some("[17.0.8,17.0.9)".to_string()), exact_version_range("17.0.8"));
+        assert_eq!(Some("[17.0.8.1,17.0.8.2)".to_string()), exact_version_range("17.0.8.1+1"));
+        assert_eq!(Some("[8.0.382,8.0.383)".to_string()), exact_version_range("8.0.382"));
+        assert_eq!(None, exact_version_range("latest"));
+    }
+}
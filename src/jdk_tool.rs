@@ -0,0 +1,130 @@
+//! # JDK tools
+//!
+//! When invoked as `java`, `javac`, `jshell` etc., javabox runs that tool from the JDK of the current project,
+//! so that e.g. `java -version` in a repository prints the version the project builds with.
+use std::env::current_dir;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Stdio;
+
+use dir::home_dir;
+
+use crate::config::{JavaboxConfig, JavaConfig};
+use crate::config::global::{self, Setting};
+use crate::config::lock::JavaboxLock;
+use crate::jdk::JavaSettings;
+use crate::{gradle, jdk, mvn, utils};
+
+/// Launchers in `bin/` of a JDK
+pub const JDK_TOOLS: [&str; 29] = [
+    "jar", "jarsigner", "java", "javac", "javadoc", "javap", "jcmd", "jconsole", "jdb", "jdeprscan",
+    "jdeps", "jfr", "jhsdb", "jimage", "jinfo", "jlink", "jmap", "jmod", "jpackage", "jps",
+    "jrunscript", "jshell", "jstack", "jstat", "jstatd", "jwebserver", "keytool", "rmiregistry", "serialver",
+];
+
+/// The JDK tool that javabox was invoked as, if any
+pub fn jdk_tool_name(name: &str) -> Option<&str> {
+    JDK_TOOLS.contains(&name).then_some(name)
+}
+
+pub fn run_jdk_tool_here(tool: &str, args: &[OsString]) -> anyhow::Result<i32> {
+    run_jdk_tool(&current_dir()?, tool, args)
+}
+
+pub fn run_jdk_tool(cwd: &Path, tool: &str, args: &[OsString]) -> anyhow::Result<i32> {
+    log::trace!("run_jdk_tool({}, {tool})", cwd.display());
+    let (settings, project_dir) = project_java(cwd)?;
+    let settings = settings.effective()?;
+    let version = match settings.version {
+        Some(version) => version,
+        None => fallback_version(tool, settings.vendor.as_ref())?,
+    };
+    let java = JavaConfig { version: version.value, vendor: settings.vendor.map(|vendor| vendor.value) };
+    let lock = match project_dir {
+        Some(project_dir) => JavaboxLock::load(project_dir)?,
+        None => None,
+    };
+    let (java_home, _) = jdk::resolve_java(&java, lock.as_ref())?;
+    let executable = java_home.join("bin").join(format!("{tool}{}", std::env::consts::EXE_SUFFIX));
+    if !executable.is_file() {
+        // like `jwebserver` in JDK 17, or `jshell` in JDK 8
+        anyhow::bail!("JDK {} does not provide '{tool}'", java_home.display());
+    }
+    log::debug!("Running {}", executable.display());
+    let mut command = std::process::Command::new(&executable);
+    command.args(args);
+    command.stdout(Stdio::inherit());
+    command.stderr(Stdio::inherit());
+    jdk::apply_java_home(&mut command, &java_home)?;
+    Ok(utils::run_in_place(&mut command)?)
+}
+
+/// Outside of projects, and without `java.default` in user configuration, the tool comes from the newest installed JDK;
+/// only when there is none, the built-in default gets installed.
+fn fallback_version(tool: &str, vendor: Option<&Setting>) -> anyhow::Result<Setting> {
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    if let Some(version) = jdk::newest_installed(&user_home, vendor.map(|vendor| vendor.value.as_str()))? {
+        log::debug!("Not in a project, running '{tool}' from the newest installed JDK {version}");
+        return Ok(Setting::new(&version, "newest installed JDK"));
+    }
+    log::warn!("Not in a project, and no java.default in {}: installing JDK {} to run '{tool}'",
+        global::config_file().display(), jdk::DEFAULT_JAVA_VERSION);
+    Ok(Setting::new(jdk::DEFAULT_JAVA_VERSION, "built-in default"))
+}
+
+/// Finds the Java settings of the nearest project containing given directory, and that project's directory.
+/// Outside of any project, there are none.
+fn project_java(cwd: &Path) -> anyhow::Result<(JavaSettings, Option<&Path>)> {
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    for dir in cwd.ancestors() {
+        let configured = if JavaboxConfig::is_inside(dir) {
+            JavaboxConfig::load(dir)?
+        } else {
+            JavaboxConfig::default()
+        };
+        let settings = jdk::project_java_settings(dir, configured.java.as_ref(), &configured.origin)?;
+        if settings.version.is_some() {
+            return Ok((settings, Some(dir)));
+        }
+        let inferred = if dir.join("pom.xml").is_file() {
+            Some(mvn::infer_java_version(dir)?)
+        } else if gradle::is_gradle_project(dir) {
+            Some(gradle::infer_java_version(dir)?)
+        } else {
+            None
+        };
+        if let Some(version) = inferred {
+            log::debug!("Java version {version} inferred from project in {}", dir.display());
+            let origin = format!("inferred from project files in {}", dir.display());
+            return Ok((JavaSettings { version: Some(Setting::new(&version, &origin)), vendor: None }, Some(dir)));
+        }
+        // never look beyond the repository or the user's home
+        if utils::is_scm_wc_root(dir) || dir == user_home {
+            break;
+        }
+    }
+    Ok((JavaSettings::default(), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::testing::TempDir;
+
+    use super::project_java;
+
+    #[test]
+    fn test_project_java() {
+        let tmp = TempDir::new("jdk-tool");
+        let module = tmp.join("app/src/main/java");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::create_dir(tmp.join(".hg")).unwrap();
+        std::fs::write(tmp.join("build.gradle"), "java { toolchain { languageVersion = JavaLanguageVersion.of(21) } }\n").unwrap();
+        assert_eq!("21", project_java(&module).unwrap().0.version.unwrap().value);
+        std::fs::write(tmp.join("app/jdk.version"), "VENDOR=oracle\nMAJOR=11\n").unwrap();
+        let (java, project_dir) = project_java(&module).unwrap();
+        assert_eq!(Some(tmp.join("app").as_path()), project_dir);
+        let java = java.to_config().unwrap();
+        assert_eq!("11", java.version);
+        assert_eq!(Some("oracle".to_string()), java.vendor);
+    }
+}
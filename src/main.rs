@@ -1,6 +1,11 @@
 use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+
 use log::LevelFilter;
 
+/// Exit code for an unknown alias name, the same as shells use for unknown commands
+const EXIT_UNKNOWN_ALIAS: i32 = 127;
 
 fn main() -> anyhow::Result<()> {
     pretty_env_logger::formatted_timed_builder()
@@ -8,25 +13,32 @@ fn main() -> anyhow::Result<()> {
         .filter_level(LevelFilter::Info)
         .filter_module("serde_xml_rs", LevelFilter::Info)
         .filter_module("rustls", LevelFilter::Info)
-        .filter_module("zip_extract", LevelFilter::Debug)
         .parse_env("JAVABOX_LOG")
         .init();
 
-    let exe = env::args().next().unwrap();
-    // println!("executable full name is '{exe}'");
-    let exe = match exe.rfind(std::path::MAIN_SEPARATOR) {
-        None => exe.as_str(),
-        Some(n) => &exe[n+1..]
-    };
-    // println!("executable name is '{exe}'");
-    let exit_code = match exe {
-        "mvnw" |
-        "mvn" => mvn::run_mvn_here()?,
-        "gradlew" |
-        "gradle" => gradle::run_gradle()?,
-        "javabox.exe" |
-        "javabox" => javabox::run_javabox()?,
-        _ => panic!("Unsupported alias name: {exe}")
+    let args: Vec<OsString> = env::args_os().collect();
+    let exe = args.first()
+        .map(Path::new)
+        .and_then(|arg0| arg0.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let exe = exe.strip_suffix(env::consts::EXE_SUFFIX).unwrap_or(&exe);
+    let exit_code = match Tool::from_alias(exe) {
+        Some(Tool::Javabox) => {
+            // explicit multicall form, like `javabox mvn clean install`
+            let args = skip_javabox_names(&args[1..]);
+            match args.first().and_then(|arg| arg.to_str()).and_then(Tool::from_alias) {
+                Some(Tool::Javabox) | None => javabox::run_javabox(args)?,
+                Some(tool) => tool.run(&args[1..])?,
+            }
+        }
+        Some(tool) => tool.run(&args[1..])?,
+        None => {
+            eprintln!("javabox: unsupported alias name '{exe}'");
+            eprintln!("Run it as mvn, mvnw, gradle, gradlew, a JDK tool like java or javac, or as javabox.");
+            eprintln!("Any of these can also be run explicitly: javabox <alias> [args...]");
+            std::process::exit(EXIT_UNKNOWN_ALIAS);
+        }
     };
     if exit_code != 0 {
         log::error!("Returning with exit code {exit_code}");
@@ -35,10 +47,90 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Drops leading `javabox` names, so that `javabox javabox lock` is the same as `javabox lock`
+fn skip_javabox_names(args: &[OsString]) -> &[OsString] {
+    let count = args.iter()
+        .take_while(|arg| arg.to_str().and_then(Tool::from_alias) == Some(Tool::Javabox))
+        .count();
+    &args[count..]
+}
+
+/// What the multicall binary can act as
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tool<'a> {
+    Maven,
+    Gradle,
+    Javabox,
+    Jdk(&'a str),
+}
+
+impl<'a> Tool<'a> {
+    /// Recognizes the name javabox was invoked as; anything starting with `javabox` is javabox itself,
+    /// like a renamed `javabox-0.2`.
+    fn from_alias(name: &'a str) -> Option<Self> {
+        match name {
+            "mvn" | "mvnw" => Some(Tool::Maven),
+            "gradle" | "gradlew" => Some(Tool::Gradle),
+            name if name.starts_with("javabox") => Some(Tool::Javabox),
+            name => jdk_tool::jdk_tool_name(name).map(Tool::Jdk),
+        }
+    }
+
+    /// Switches that put the tool itself offline; javabox then stays offline too
+    fn offline_flags(self) -> &'static [&'static str] {
+        match self {
+            Tool::Maven => &["-o", "--offline"],
+            Tool::Gradle => &["--offline"],
+            Tool::Javabox | Tool::Jdk(_) => &[],
+        }
+    }
+
+    fn run(self, args: &[OsString]) -> anyhow::Result<i32> {
+        // like `@@jdk=21::`, never passed to the tool
+        let args = &config::overrides::extract(args)?;
+        if args.iter().any(|arg| self.offline_flags().iter().any(|flag| arg == flag)) {
+            utils::set_offline();
+        }
+        match self {
+            Tool::Maven => mvn::run_mvn_here(args),
+            Tool::Gradle => gradle::run_gradle(args),
+            Tool::Jdk(tool) => jdk_tool::run_jdk_tool_here(tool, args),
+            Tool::Javabox => javabox::run_javabox(args),
+        }
+    }
+}
+
 mod mvn;
 mod gradle;
 mod javabox;
 mod utils;
 mod java_hash;
+mod jdk;
+mod jdk_tool;
 
 mod config;
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::{Tool, skip_javabox_names};
+
+    #[test]
+    fn test_from_alias() {
+        assert_eq!(Some(Tool::Maven), Tool::from_alias("mvnw"));
+        assert_eq!(Some(Tool::Gradle), Tool::from_alias("gradle"));
+        assert_eq!(Some(Tool::Javabox), Tool::from_alias("javabox-0.2"));
+        assert_eq!(Some(Tool::Javabox), Tool::from_alias("javabox.debug"));
+        assert_eq!(Some(Tool::Jdk("jshell")), Tool::from_alias("jshell"));
+        assert_eq!(None, Tool::from_alias("maven"));
+    }
+
+    #[test]
+    fn test_skip_javabox_names() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(args(&["lock"]), skip_javabox_names(&args(&["javabox", "javabox", "lock"])));
+        assert_eq!(args(&["mvn", "javabox"]), skip_javabox_names(&args(&["mvn", "javabox"])));
+        assert!(skip_javabox_names(&args(&["javabox"])).is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 //!
 use std::collections::HashMap;
 use std::env::current_dir;
+use std::ffi::OsString;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -9,108 +10,331 @@ use std::str::FromStr;
 use dir::home_dir;
 use url::Url;
 
-use crate::{java_hash, utils};
+use crate::{java_hash, jdk, utils};
 use crate::config::{JavaboxConfig, JavaConfig, MavenConfig};
+use crate::config::env::ProjectEnv;
+use crate::config::global::{GlobalConfig, Setting};
+use crate::config::lock::{self, JavaboxLock, Source, Sources};
 use crate::mvn::centralrepo::APACHE_MAVEN_DIST_URL_BASE;
-use crate::utils::download;
+use crate::mvn::options::MavenOptions;
+use crate::utils::archive::{ArchiveFormat, extract_archive};
+use crate::utils::download_distribution;
+use crate::utils::version::{ComparableVersion, VersionConstraint};
 
-pub fn run_mvn_here() -> anyhow::Result<i32> {
-    run_mvn(&current_dir()?)
+/// What `maven-compiler-plugin` uses when the project does not say
+const DEFAULT_JAVA_VERSION: &str = "1.8";
+
+/// Environment variables honored by maven-wrapper
+const MAVEN_USER_HOME: &str = "MAVEN_USER_HOME";
+const MVNW_REPOURL: &str = "MVNW_REPOURL";
+const MVNW_USERNAME: &str = "MVNW_USERNAME";
+const MVNW_PASSWORD: &str = "MVNW_PASSWORD";
+/// `MVNW_REPOURL` replaces what precedes this in distribution URLs
+const MVNW_REPO_PATTERN: &str = "/org/apache/maven/";
+
+pub fn run_mvn_here(args: &[OsString]) -> anyhow::Result<i32> {
+    run_mvn(&current_dir()?, args)
 }
 
-pub fn run_mvn(cwd: &Path) -> anyhow::Result<i32> {
+pub fn run_mvn(cwd: &Path, args: &[OsString]) -> anyhow::Result<i32> {
     log::trace!("run_mvn({})", cwd.display());
     let mvn_env = MavenEnv::load_or_infer(cwd)?;
-    let exit_code = mvn_env.execute(cwd)?;
+    let exit_code = mvn_env.execute(cwd, args)?;
     Ok(exit_code)
 }
 
-fn get_maven_home(user_home: &Path, distribution_url: &String) -> std::io::Result<PathBuf> {
-    let distribution_url = Url::from_str(distribution_url)
-        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
-    let upath = distribution_url.path();
+/// Where maven-wrapper installs distributions
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DistributionType {
+    /// `bin`, `script` and `source` wrappers run the wrapper jar, it installs into `dists/{archive name}/{URI hash}/{name}`
+    Jar,
+    /// `only-script` wrapper installs into `dists/{name}/{string hash}`
+    OnlyScript,
+}
 
-    match upath.rfind('/') {
-        None => {
-            log::warn!("Invalid distribution URL: {distribution_url}");
-            Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange distribution URL: {distribution_url}")))
-        }
-        Some(n) => {
-            let zip_name = &upath[n + 1..];
-            let base_name = zip_name.replace(".zip", "");
-            let dist_name = base_name.replace("-bin", "");
-            let url_hash = java_hash::java_uri_hash(&distribution_url);
-            let maven_base = user_home.join(format!(".m2/wrapper/dists/{base_name}/{url_hash:x}"));
-            let maven_home = maven_base.join(dist_name);
-            if !maven_home.is_dir() {
-                let zip_path = maven_base.join(zip_name);
-                // if the zip is missing, download it first (verify checksums!)
-                if !zip_path.is_file() {
-                    let _ = std::fs::create_dir_all(maven_base);
-                    download(&distribution_url, &zip_path)?;
-                }
-                let zip = std::fs::File::open(&zip_path)?;
-                log::trace!("Extracting {} to {}", zip_path.to_string_lossy(), maven_home.to_string_lossy());
-                zip_extract::extract(zip, &maven_home, true)
-                    .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("Failed to extract file: {} :: {e:?}", zip_path.display())))?;
+impl DistributionType {
+    fn parse(distribution_type: Option<&str>) -> std::io::Result<Self> {
+        match distribution_type.map(str::trim) {
+            None | Some("") | Some("bin") | Some("script") | Some("source") => Ok(DistributionType::Jar),
+            Some("only-script") => Ok(DistributionType::OnlyScript),
+            Some(other) => Err(std::io::Error::new(ErrorKind::InvalidInput,
+                format!("Unsupported distributionType '{other}'; use bin, script, source or only-script"))),
+        }
+    }
+}
+
+/// `~/.m2`, unless `MAVEN_USER_HOME` says otherwise; like maven-wrapper, distributions are kept under `wrapper/dists` in there.
+pub fn maven_user_home() -> PathBuf {
+    match std::env::var_os(MAVEN_USER_HOME).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir().expect("There is no HOME directory?!").join(".m2"),
+    }
+}
+
+/// With `MVNW_REPOURL`, distributions come from that repository instead; the path from `/org/apache/maven/` on is kept.
+fn mirrored_url(distribution_url: &str) -> String {
+    match std::env::var(MVNW_REPOURL) {
+        Ok(repo_url) if !repo_url.is_empty() => match distribution_url.split_once(MVNW_REPO_PATTERN) {
+            Some((_, path)) => format!("{repo_url}{MVNW_REPO_PATTERN}{path}"),
+            None => {
+                log::warn!("{MVNW_REPOURL} does not apply to {distribution_url}, it has no {MVNW_REPO_PATTERN} in it");
+                distribution_url.to_string()
             }
-            log::debug!("maven_home={}", maven_home.display());
-            Ok(maven_home)
+        },
+        _ => distribution_url.to_string(),
+    }
+}
+
+/// Adds `MVNW_USERNAME` and `MVNW_PASSWORD` to the URL, for repositories that need authentication
+fn with_credentials(mut url: Url) -> Url {
+    if let (Ok(username), Ok(password)) = (std::env::var(MVNW_USERNAME), std::env::var(MVNW_PASSWORD)) {
+        if url.set_username(&username).and_then(|_| url.set_password(Some(&password))).is_err() {
+            log::warn!("Cannot use {MVNW_USERNAME} and {MVNW_PASSWORD} with {url}");
         }
     }
+    url
 }
 
-struct MavenEnv {
-    maven_bin: PathBuf,
-    java_home: PathBuf,
-    //TODO env, properties etc
+/// The installation directory of the distribution, the same one as maven-wrapper uses
+fn maven_home_dir(maven_user_home: &Path, distribution_url: &str, distribution_type: DistributionType) -> std::io::Result<PathBuf> {
+    let parsed = Url::from_str(distribution_url)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
+    let archive_name = match parsed.path().rsplit_once('/') {
+        Some((_, archive_name)) if !archive_name.is_empty() => archive_name,
+        _ => return Err(std::io::Error::new(ErrorKind::Unsupported, format!("Strange distribution URL: {distribution_url}"))),
+    };
+    let base_name = ArchiveFormat::base_name(archive_name);
+    let dist_name = base_name.replace("-bin", "");
+    let dists = maven_user_home.join("wrapper/dists");
+    Ok(match distribution_type {
+        // maven-wrapper hashes the URL as written, so it must be hashed before `Url` normalizes it
+        DistributionType::Jar => dists.join(format!("{base_name}/{:x}/{dist_name}", java_hash::java_uri_hash(distribution_url))),
+        DistributionType::OnlyScript => dists.join(format!("{dist_name}/{:x}", mvnw_hash(distribution_url))),
+    })
+}
+
+/// `hash_string` of the `only-script` mvnw
+fn mvnw_hash(text: &str) -> u32 {
+    text.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32))
+}
+
+fn get_maven_home(maven_user_home: &Path, distribution_url: &str, distribution_sha256_sum: Option<&str>, distribution_type: DistributionType) -> std::io::Result<PathBuf> {
+    let distribution_url = mirrored_url(distribution_url);
+    let maven_home = maven_home_dir(maven_user_home, &distribution_url, distribution_type)?;
+    if !maven_home.is_dir() {
+        let distribution_url = Url::from_str(&distribution_url)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Bad URL: {distribution_url} :: {e:?}")))?;
+        let archive_name = distribution_url.path().rsplit('/').next().unwrap_or_default();
+        let maven_base = maven_home.parent().unwrap();
+        let archive_path = maven_base.join(archive_name);
+        // if the archive is missing, download it first
+        if !archive_path.is_file() {
+            let _ = std::fs::create_dir_all(maven_base);
+            download_distribution(&with_credentials(distribution_url), &archive_path, distribution_sha256_sum, None)?;
+        }
+        log::trace!("Extracting {} to {}", archive_path.to_string_lossy(), maven_home.to_string_lossy());
+        extract_archive(&archive_path, &maven_home)?;
+    }
+    log::debug!("maven_home={}", maven_home.display());
+    Ok(maven_home)
+}
+
+/// Resolved tooling of a Maven project
+pub struct MavenEnv {
+    pub maven_home: PathBuf,
+    pub java_home: Option<PathBuf>,
+    /// the reactor root
+    project_dir: PathBuf,
+    /// the module where mvn was invoked
+    module_dir: PathBuf,
+    /// see [MavenConfig::build_from_root]
+    build_from_root: bool,
+    sources: Sources,
+    /// arguments and JVM options of the project
+    pub options: MavenOptions,
+    /// `[env]` and `[maven.opts]` of `javabox.toml`
+    pub env: ProjectEnv,
 }
 
 impl MavenEnv {
     /// Finds directory with root pom.xml file
     pub fn load_or_infer(cwd: &Path) -> anyhow::Result<MavenEnv> {
-        let user_home = home_dir().unwrap();
-        let config = if JavaboxConfig::is_inside(cwd) {
-            JavaboxConfig::load(cwd)?
-        } else {
-            infer_config(cwd)?
-        };
-        let maven = config.maven.as_ref().unwrap();
+        Self::resolve(cwd, false)
+    }
+
+    /// Resolves the tools of the reactor, preferring those locked in `javabox.lock` of its root unless updating it.
+    pub fn resolve(cwd: &Path, update: bool) -> anyhow::Result<MavenEnv> {
+        let (project_dir, module_dir) = find_reactor(cwd)?;
+        let maven_user_home = maven_user_home();
+        let config = JavaboxConfig::load_or_infer(&project_dir, "maven", infer_config)?;
+        let env = ProjectEnv::resolve(&config, config.maven.as_ref().map(|maven| &maven.opts), &project_dir)?;
+        let options = MavenOptions::load(&project_dir, config.maven.as_ref().unwrap(), &env)?;
+        let maven = effective_maven(config.maven.as_ref().unwrap(), &config.origin)?;
+        let maven = &maven;
+        let distribution_type = DistributionType::parse(maven.distribution_type.as_deref())?;
+        if let Some(wrapper_version) = &maven.wrapper_version {
+            log::debug!("maven-wrapper {wrapper_version}, distributionType {distribution_type:?}");
+        }
+        let lock = if update { None } else { JavaboxLock::load(&project_dir)? };
         // maven_version -> distributionUrl
         // maven_version -> MAVEN_HOME
 
-        let maven_home = get_maven_home(&user_home, &maven.download_url)?;
+        let (maven_home, maven_source) = match lock::locked(lock.as_ref(), "maven", |version| lock::satisfies(&maven.version, version))? {
+            Some(locked) => {
+                let maven_home = get_maven_home(&maven_user_home, &locked.url, Some(&locked.sha256), distribution_type)?;
+                let source = Source::from_locked(locked, maven_home.parent().unwrap_or(&maven_home));
+                (maven_home, source)
+            }
+            None => {
+                let (version, download_url) = resolve_distribution(&maven_user_home, maven)?;
+                // a pinned checksum belongs to a particular version, not to a constraint
+                let pinned_checksum = maven.distribution_sha256_sum.as_deref()
+                    .filter(|_| !VersionConstraint::is_constraint(&maven.version));
+                let maven_home = get_maven_home(&maven_user_home, &download_url, pinned_checksum, distribution_type)?;
+                let source = Source::new(&version, &download_url, maven_home.parent().unwrap_or(&maven_home), pinned_checksum);
+                (maven_home, source)
+            }
+        };
+        let java = match jdk::java_requirement(&project_dir, config.java.as_ref(), &config.origin)? {
+            None => None,
+            Some(java) => Some(jdk::resolve_java(&java, lock.as_ref())?),
+        };
+        let (java_home, java_source) = java.unzip();
+        let sources = Sources { java: java_source, maven: Some(maven_source), gradle: None };
 
         // determine maven_home directory based on maven_version and customizations
         // if empty:
         // - download maven if not downloaded yet
         // - expand downloaded to maven_home
 
-        // download java if needed, pass it to JAVA_HOME and PATH
         // maybe other required tooling
         Ok(MavenEnv {
-            maven_bin: maven_home.join("bin/mvn"),
-            java_home: Default::default(),
+            maven_home,
+            java_home,
+            project_dir,
+            module_dir,
+            build_from_root: config.maven.as_ref().is_some_and(|maven| maven.build_from_root),
+            sources,
+            options,
+            env,
         })
     }
 
-    pub fn execute(&self, cwd: &Path) -> std::io::Result<i32> {
-        log::info!("Running {} in project {}", self.maven_bin.display(), cwd.display());
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    /// The lock entries of the tools in use
+    pub fn lock(&self) -> anyhow::Result<JavaboxLock> {
+        self.sources.lock()
+    }
 
-        let mut command = std::process::Command::new(&self.maven_bin);
-        command.current_dir(cwd);
-        command.args(std::env::args().skip(1));
+    /// Runs Maven in the module where it was started, or from the reactor root when configured so, see [launch_dir].
+    pub fn execute(&self, cwd: &Path, args: &[OsString]) -> std::io::Result<i32> {
+        let maven_bin = self.maven_home.join("bin/mvn");
+        let (dir, module) = launch_dir(&self.project_dir, &self.module_dir, cwd, args, self.build_from_root);
+        let mut command = std::process::Command::new(&maven_bin);
+        command.current_dir(&dir);
+        match module {
+            Some(module) => {
+                log::info!("Running {} in project {} for module {module}", maven_bin.display(), dir.display());
+                command.args(["-pl", &module, "-am"]);
+            }
+            None => log::info!("Running {} in project {}", maven_bin.display(), dir.display()),
+        }
+        command.args(self.options.args());
+        command.args(&self.env.properties);
+        command.args(args);
+        self.env.apply(&mut command);
+        if let Some(maven_opts) = self.options.maven_opts() {
+            command.env(options::MAVEN_OPTS, maven_opts);
+        }
+        log::debug!("Maven arguments: {}", self.options.effective_args().join(" "));
+        log::debug!("Maven JVM options: {}", self.options.effective_jvm_options());
         command.stdout(Stdio::inherit());
         command.stderr(Stdio::inherit());
-        command.env("JAVA_HOME", self.java_home.display().to_string());
-        let status = command.status()?;
-        match status.code() {
-            None => Err(std::io::Error::new(ErrorKind::BrokenPipe, "Interrupted")),
-            Some(code) => Ok(code)
+        if let Some(java_home) = &self.java_home {
+            jdk::apply_java_home(&mut command, java_home)?;
         }
+        utils::run_in_place(&mut command)
     }
 }
 
+/// Finds the reactor root of the project containing given directory, and the module in there.
+/// The root is the nearest directory with `.mvn/`, like Maven's `maven.multiModuleProjectDirectory`;
+/// without one, it is the topmost POM that aggregates the module through `<modules>`.
+/// Neither search goes beyond the SCM working copy or the user's home.
+pub fn find_reactor(cwd: &Path) -> anyhow::Result<(PathBuf, PathBuf)> {
+    let user_home = home_dir().expect("There is no HOME directory?!");
+    let cwd = cwd.canonicalize()?;
+    let is_top = |dir: &Path| utils::is_scm_wc_root(dir) || dir == user_home;
+    let mut module_dir = None;
+    for dir in cwd.ancestors() {
+        if dir.join("pom.xml").is_file() {
+            module_dir = Some(dir.to_path_buf());
+            break;
+        }
+        if is_top(dir) {
+            break;
+        }
+    }
+    let module_dir = module_dir.ok_or(anyhow::anyhow!("No pom.xml file in {} or its parents", cwd.display()))?;
+    for dir in module_dir.ancestors() {
+        if dir.join(".mvn").is_dir() && dir.join("pom.xml").is_file() {
+            log::debug!("Reactor root with .mvn: {}", dir.display());
+            return Ok((dir.to_path_buf(), module_dir));
+        }
+        if is_top(dir) {
+            break;
+        }
+    }
+    // modules may be nested deeper, like `libs/core`, so directories without POM are skipped
+    let mut project_dir = module_dir.clone();
+    // the module itself may already be the SCM root or home
+    let parent_dir = if is_top(&module_dir) { None } else { module_dir.parent() };
+    for dir in parent_dir.into_iter().flat_map(Path::ancestors) {
+        let pom_file = dir.join("pom.xml");
+        if pom_file.is_file() {
+            let aggregated = pom::module_dirs(&pom_file).unwrap_or_default().iter()
+                .filter_map(|module| module.canonicalize().ok())
+                .any(|module| module == project_dir);
+            if !aggregated {
+                break;
+            }
+            project_dir = dir.to_path_buf();
+        }
+        if is_top(dir) {
+            break;
+        }
+    }
+    log::debug!("Reactor root: {}, module: {}", project_dir.display(), module_dir.display());
+    Ok((project_dir, module_dir))
+}
+
+/// Where Maven runs, and the module to select there, if any.
+/// Maven runs in the module, also when started where there is no POM, like `src/main/java`. Building from the root,
+/// it runs there and selects the module; arguments choosing the projects or POM file always keep the current directory.
+fn launch_dir(project_dir: &Path, module_dir: &Path, cwd: &Path, args: &[OsString], from_root: bool) -> (PathBuf, Option<String>) {
+    if selects_projects(args) {
+        return (cwd.to_path_buf(), None);
+    }
+    if !from_root {
+        return (module_dir.to_path_buf(), None);
+    }
+    let module = module_dir.strip_prefix(project_dir).ok()
+        .filter(|module| !module.as_os_str().is_empty())
+        .map(|module| module.to_string_lossy().replace('\\', "/"));
+    (project_dir.to_path_buf(), module)
+}
+
+/// Decides if the arguments choose what to build, so that the module of the current directory must not be added
+fn selects_projects(args: &[OsString]) -> bool {
+    args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+        matches!(arg, "-pl" | "--projects" | "-f" | "--file")
+            || arg.starts_with("--projects=") || arg.starts_with("--file=")
+    })
+}
+
 pub fn infer_config(cwd: &Path) -> anyhow::Result<JavaboxConfig> {
     let mut props = HashMap::new();
     let mwp = cwd.join(".mvn/wrapper/maven-wrapper.properties");
@@ -123,40 +347,242 @@ pub fn infer_config(cwd: &Path) -> anyhow::Result<JavaboxConfig> {
         anyhow::bail!("No pom.xml file in {}", cwd.display());
     }
     // maven version: from wrapper or default
-    // TODO: consider reading properties and compiler plugin config from pom.xml
-    let maven_version = match maven_version_from_wrapper(props) {
-        None => centralrepo::maven_last_stable_version()?,
-        Some(maven_version) => maven_version
+    let (maven_version, download_url) = match props.get("distributionUrl") {
+        Some(distribution_url) => {
+            // a mirror or repository manager may name the distribution differently; its URL is then used as it is
+            let maven_version = maven_version_from_url(distribution_url).unwrap_or_else(|| {
+                log::debug!("Cannot tell Maven version from distributionUrl in {}: {distribution_url}", mwp.display());
+                String::new()
+            });
+            (maven_version, distribution_url.clone())
+        }
+        None => {
+            let maven_version = match utils::is_offline() {
+                true => newest_installed_maven(&maven_user_home())?,
+                false => centralrepo::maven_last_stable_version()?,
+            };
+            let download_url = default_download_url(&maven_version);
+            (maven_version, download_url)
+        }
     };
-    let download_url = format!("{APACHE_MAVEN_DIST_URL_BASE}/{maven_version}/apache-maven-{maven_version}-bin.zip").parse()?;
     let maven = MavenConfig {
-        version: maven_version.to_string(),
+        version: maven_version,
         download_url,
+        distribution_sha256_sum: props.get("distributionSha256Sum").cloned(),
+        distribution_type: props.get("distributionType").cloned(),
+        wrapper_version: props.get("wrapperVersion").cloned(),
+        ..Default::default()
     };
-    let java_version = "1.8".to_string();
+    let java_version = infer_java_version(cwd)?;
     Ok(JavaboxConfig {
-        java: Some(JavaConfig { version: java_version }),
+        java: Some(JavaConfig { version: java_version, vendor: None }),
         maven: Some(maven),
         ..Default::default()
     })
 }
 
-fn maven_version_from_wrapper<'a>(props: HashMap<String, String>) -> Option<String> {
-    match props.get("distributionUrl") {
-        None => None,
-        Some(dist) => {
-            log::warn!("dist={dist}");
-            match dist[APACHE_MAVEN_DIST_URL_BASE.len()+1..].split('/')
-                // .skip(1)
-                .next() {
-                None => None,
-                Some(version) => {
-                    log::debug!("{dist} --> '{version}'");
-                    Some(version.to_string())
-                }
-            }
+/// Layers environment and user configuration over the project's Maven; for another version, the project's distribution does not apply.
+fn effective_maven(maven: &MavenConfig, origin: &str) -> anyhow::Result<MavenConfig> {
+    let project = Setting::new(&maven.version, origin);
+    match GlobalConfig::get()?.version("maven", Some(project.clone()), None) {
+        Some(setting) if setting.value != project.value => {
+            log::debug!("Using Maven {} from {} instead of {}", setting.value, setting.origin, project.value);
+            // the layout of installed distributions still applies
+            Ok(MavenConfig {
+                distribution_type: maven.distribution_type.clone(),
+                ..MavenConfig::from(setting.value)
+            })
         }
+        _ => Ok(maven.clone()),
     }
 }
 
+fn default_download_url(maven_version: &str) -> String {
+    format!("{APACHE_MAVEN_DIST_URL_BASE}/{maven_version}/apache-maven-{maven_version}-bin.zip")
+}
+
+/// The version and distribution URL to use; for a version constraint, it is the newest satisfying Maven that is released or installed.
+/// Without `download_url`, the default location is used.
+fn resolve_distribution(maven_user_home: &Path, maven: &MavenConfig) -> anyhow::Result<(String, String)> {
+    if !VersionConstraint::is_constraint(&maven.version) {
+        let download_url = match maven.download_url.as_str() {
+            "" => default_download_url(&maven.version),
+            download_url => download_url.to_string(),
+        };
+        return Ok((maven.version.clone(), download_url));
+    }
+    let constraint = VersionConstraint::parse(&maven.version)?;
+    let mut candidates = installed_mavens(maven_user_home);
+    if !utils::is_offline() {
+        candidates.extend(centralrepo::maven_versions()?);
+    }
+    match constraint.best(candidates.iter().map(String::as_str)) {
+        Some(version) => {
+            log::debug!("Maven {version} satisfies '{constraint}'");
+            Ok((version.to_string(), default_download_url(version)))
+        }
+        None => Err(constraint.unsatisfied("Maven", &candidates).into()),
+    }
+}
+
+/// Checks for new Maven releases now, regardless of the update check interval
+pub fn refresh_versions() -> std::io::Result<()> {
+    centralrepo::refresh_versions()
+}
+
+/// Versions of Maven installed from the default location, by either kind of maven-wrapper
+fn installed_mavens(maven_user_home: &Path) -> Vec<String> {
+    let dists = maven_user_home.join("wrapper/dists");
+    let mut versions: Vec<String> = std::fs::read_dir(dists).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let version = name.strip_prefix("apache-maven-")?;
+            let version = version.strip_suffix("-bin").unwrap_or(version).to_string();
+            let distribution_url = mirrored_url(&default_download_url(&version));
+            [DistributionType::Jar, DistributionType::OnlyScript].into_iter()
+                .any(|distribution_type| maven_home_dir(maven_user_home, &distribution_url, distribution_type).is_ok_and(|home| home.is_dir()))
+                .then_some(version)
+        })
+        .collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// Newest Maven installed from the default location; offline mode uses it instead of the latest release.
+fn newest_installed_maven(maven_user_home: &Path) -> std::io::Result<String> {
+    installed_mavens(maven_user_home).into_iter()
+        .max_by_key(|version| ComparableVersion::new(version))
+        .ok_or(std::io::Error::new(ErrorKind::NotFound, format!("Offline mode: no Maven distribution installed in {}", maven_user_home.join("wrapper/dists").display())))
+}
+
+/// Infers the Java version from `pom.xml` in given directory, and its parents.
+pub fn infer_java_version(cwd: &Path) -> std::io::Result<String> {
+    let pom = cwd.join("pom.xml");
+    match pom::infer_java_version(&pom)? {
+        Some(java_version) => Ok(java_version),
+        None => {
+            log::debug!("No java version in {}, assuming the compiler plugin default {DEFAULT_JAVA_VERSION}", pom.display());
+            Ok(DEFAULT_JAVA_VERSION.to_string())
+        }
+    }
+}
+
+/// Maven version from the distribution archive name, like `3.9.6` for `apache-maven-3.9.6-bin.zip` in any repository
+fn maven_version_from_url(distribution_url: &str) -> Option<String> {
+    let archive_name = distribution_url.rsplit('/').next()?;
+    let version = ArchiveFormat::base_name(archive_name).strip_prefix("apache-maven-")?;
+    let version = version.strip_suffix("-bin").unwrap_or(version);
+    (!version.is_empty()).then(|| version.to_string())
+}
+
 mod centralrepo;
+mod options;
+mod pom;
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    use crate::utils::testing::TempDir;
+
+    use super::{DistributionType, find_reactor, infer_config, launch_dir, maven_home_dir, maven_version_from_url, mvnw_hash, selects_projects};
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_maven_version_from_url() {
+        assert_eq!(Some("3.9.6".to_string()), maven_version_from_url("https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/3.9.6/apache-maven-3.9.6-bin.zip"));
+        assert_eq!(Some("3.8.8".to_string()), maven_version_from_url("https://nexus.example.com/repository/public/org/apache/maven/apache-maven/3.8.8/apache-maven-3.8.8-bin.tar.gz"));
+        assert_eq!(Some("4.0.0-rc-2".to_string()), maven_version_from_url("https://artifactory.example.com/maven/apache-maven-4.0.0-rc-2-bin.zip"));
+        assert_eq!(None, maven_version_from_url("https://example.com/dists/maven.zip"));
+    }
+
+    #[test]
+    fn test_maven_home_dir() {
+        let url = "https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/3.9.6/apache-maven-3.9.6-bin.zip";
+        assert_eq!(0xa53741d1, mvnw_hash(url));
+        let m2 = Path::new("/home/user/.m2");
+        assert_eq!(m2.join("wrapper/dists/apache-maven-3.9.6/a53741d1"), maven_home_dir(m2, url, DistributionType::OnlyScript).unwrap());
+        assert!(maven_home_dir(m2, url, DistributionType::Jar).unwrap().ends_with("apache-maven-3.9.6"));
+        assert_eq!(DistributionType::OnlyScript, DistributionType::parse(Some("only-script")).unwrap());
+        assert_eq!(DistributionType::Jar, DistributionType::parse(None).unwrap());
+        assert!(DistributionType::parse(Some("zip")).is_err());
+    }
+
+    #[test]
+    fn test_find_reactor() {
+        let tmp = TempDir::new("reactor");
+        write(&tmp.join(".git/config"), "");
+        write(&tmp.join("pom.xml"), "<project><modules><module>app</module></modules>\
+            <profiles><profile><modules><module>libs/core/pom.xml</module></modules></profile></profiles></project>");
+        write(&tmp.join("app/pom.xml"), "<project/>");
+        write(&tmp.join("app/src/main/java/App.java"), "");
+        write(&tmp.join("libs/core/pom.xml"), "<project/>");
+        write(&tmp.join("tools/pom.xml"), "<project/>");
+        write(&tmp.join("site/.mvn/maven.config"), "");
+        write(&tmp.join("site/pom.xml"), "<project/>");
+        write(&tmp.join("site/docs/pom.xml"), "<project/>");
+        let tmp = tmp.canonicalize().unwrap();
+        assert_eq!((tmp.to_path_buf(), tmp.join("app")), find_reactor(&tmp.join("app/src/main/java")).unwrap());
+        assert_eq!((tmp.to_path_buf(), tmp.join("libs/core")), find_reactor(&tmp.join("libs/core")).unwrap());
+        assert_eq!((tmp.to_path_buf(), tmp.to_path_buf()), find_reactor(&tmp).unwrap());
+        assert_eq!((tmp.join("tools"), tmp.join("tools")), find_reactor(&tmp.join("tools")).unwrap());
+        assert_eq!((tmp.join("site"), tmp.join("site/docs")), find_reactor(&tmp.join("site/docs")).unwrap());
+        // a nested working copy is another project
+        std::fs::create_dir_all(tmp.join("vendor/lib/src")).unwrap();
+        write(&tmp.join("vendor/lib/.git/config"), "");
+        assert!(find_reactor(&tmp.join("vendor/lib/src")).is_err());
+        write(&tmp.join("vendor/pom.xml"), "<project><modules><module>lib</module></modules></project>");
+        write(&tmp.join("vendor/lib/pom.xml"), "<project/>");
+        assert_eq!((tmp.join("vendor/lib"), tmp.join("vendor/lib")), find_reactor(&tmp.join("vendor/lib/src")).unwrap());
+    }
+
+    #[test]
+    fn test_launch_dir() {
+        let tmp = TempDir::new("launch-dir");
+        write(&tmp.join(".git/config"), "");
+        write(&tmp.join("pom.xml"), "<project><modules><module>app</module></modules></project>");
+        write(&tmp.join("src/main/java/Main.java"), "");
+        write(&tmp.join("app/pom.xml"), "<project/>");
+        write(&tmp.join("app/src/main/java/App.java"), "");
+        let tmp = tmp.canonicalize().unwrap();
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        // the root module, started where there is no POM
+        let cwd = tmp.join("src/main/java");
+        let (project_dir, module_dir) = find_reactor(&cwd).unwrap();
+        assert_eq!((tmp.to_path_buf(), None), launch_dir(&project_dir, &module_dir, &cwd, &args(&["test"]), false));
+        assert_eq!((tmp.to_path_buf(), None), launch_dir(&project_dir, &module_dir, &cwd, &args(&["test"]), true));
+        let cwd = tmp.join("app/src/main/java");
+        let (project_dir, module_dir) = find_reactor(&cwd).unwrap();
+        // by default, in the module
+        assert_eq!((tmp.join("app"), None), launch_dir(&project_dir, &module_dir, &cwd, &args(&["exec:java"]), false));
+        assert_eq!((tmp.to_path_buf(), Some("app".to_string())), launch_dir(&project_dir, &module_dir, &cwd, &args(&["test"]), true));
+        for from_root in [false, true] {
+            assert_eq!((cwd.clone(), None), launch_dir(&project_dir, &module_dir, &cwd, &args(&["-f", "../../../pom.xml"]), from_root));
+        }
+    }
+
+    #[test]
+    fn test_selects_projects() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert!(!selects_projects(&args(&["clean", "install", "-fae"])));
+        assert!(selects_projects(&args(&["-pl", "app", "test"])));
+        assert!(selects_projects(&args(&["--file=other/pom.xml", "test"])));
+    }
+
+    #[test]
+    fn test_infer_config_custom_distribution() {
+        let tmp = TempDir::new("maven-infer");
+        let url = "https://artifactory.local/maven-dist/acme-maven.zip";
+        write(&tmp.join(".mvn/wrapper/maven-wrapper.properties"), &format!("distributionUrl={url}\n"));
+        write(&tmp.join("pom.xml"), "<project><properties><maven.compiler.release>17</maven.compiler.release></properties></project>");
+        let maven = infer_config(&tmp).unwrap().maven.unwrap();
+        assert_eq!("", maven.version);
+        assert_eq!(url, maven.download_url);
+    }
+}
@@ -1,35 +1,67 @@
 use std::io::ErrorKind;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
 
 use serde_derive::Deserialize;
 use url::Url;
 
-use crate::utils::download_or_reuse;
+use crate::config::global;
+use crate::utils::{self, download_or_reuse};
+use crate::utils::version::ComparableVersion;
 
 pub const APACHE_MAVEN_DIST_URL_BASE: &str = "https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven";
 const APACHE_MAVEN_DIST_METADATA_URL: &str = "https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/maven-metadata.xml";
 
 pub fn maven_last_stable_version() -> std::io::Result<String> {
-    log::trace!("find_latest_maven_distribution");
+    maven_newest_release(None)
+}
+
+/// Finds the highest GA release of Maven, optionally within a line like `3.8.*` or `3.9`.
+pub fn maven_newest_release(line: Option<&str>) -> std::io::Result<String> {
+    log::trace!("maven_newest_release({line:?})");
     let metadata_xml = load_known_versions()?;
-    let version = metadata_xml.versioning.versions.version.iter()
-        .filter(|v|!(v.contains("-alpha") || v.contains("-beta")))
-        .last()
-        .expect("No stable version available");
-    log::debug!("Last stable Maven release: {}", version);
-    Ok(version.to_owned())
+    let version = newest_release(&metadata_xml.versioning.versions.version, line)
+        .ok_or(std::io::Error::new(ErrorKind::NotFound, format!("No Maven release matches '{}'", line.unwrap_or("*"))))?;
+    log::debug!("Newest Maven release in line '{}': {version}", line.unwrap_or("*"));
+    Ok(version.to_string())
 }
 
-fn load_known_versions() -> std::io::Result<MavenMetadataXml> {
-    let user_home = dir::home_dir().unwrap();
-    let metadata_xml = user_home.join(".m2/wrapper/dists/maven-metadata.xml");
+/// All published versions of Maven
+pub fn maven_versions() -> std::io::Result<Vec<String>> {
+    Ok(load_known_versions()?.versioning.versions.version)
+}
+
+/// The highest version which is a release, and belongs to the line if given
+fn newest_release(versions: &[String], line: Option<&str>) -> Option<ComparableVersion> {
+    versions.iter()
+        .map(|version| ComparableVersion::new(version))
+        .filter(ComparableVersion::is_release)
+        .filter(|version| match line {
+            None => true,
+            Some(line) => version.is_in_line(line),
+        })
+        .max()
+}
+
+/// Where the list of Maven releases comes from, and where it is kept
+fn metadata_location() -> std::io::Result<(Url, PathBuf)> {
+    let metadata_xml = super::maven_user_home().join("wrapper/dists/maven-metadata.xml");
     let url = APACHE_MAVEN_DIST_METADATA_URL;
     let url = Url::from_str(url)
         .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, format!("Failed to download maven-matadata.xml: {} :: {e:?}", url)))?;
+    Ok((url, metadata_xml))
+}
+
+/// Checks for new Maven releases now, regardless of the update check interval
+pub fn refresh_versions() -> std::io::Result<()> {
+    let (url, metadata_xml) = metadata_location()?;
+    utils::refresh(&url, &metadata_xml)
+}
+
+fn load_known_versions() -> std::io::Result<MavenMetadataXml> {
+    let (url, metadata_xml) = metadata_location()?;
     // reuse the file for some time, they don't release maven every hour
-    // one day should be good enough
-    download_or_reuse(&url, &metadata_xml, Duration::from_secs(3600 * 24))?;
+    download_or_reuse(&url, &metadata_xml, global::update_check_interval("maven")?)?;
     // extract the latest version
     let meta = std::fs::File::open(&metadata_xml)?;
     let meta: MavenMetadataXml = serde_xml_rs::from_reader(meta)
@@ -60,3 +92,21 @@ struct MetadataVersioning {
 struct MetadataVersions {
     version: Vec<String>
 }
+
+#[cfg(test)]
+mod tests {
+    use super::newest_release;
+
+    #[test]
+    fn test_newest_release() {
+        // real order of `maven-metadata.xml`, which is not a version order
+        let versions: Vec<String> = ["3.0-alpha-1", "3.0", "3.0.5", "3.8.8", "3.9.0", "3.9.6", "3.8.1", "4.0.0-alpha-13",
+            "4.0.0-beta-3", "4.0.0-rc-1", "3.9.10-rc-1", "3.9.9"]
+            .iter().map(|v| v.to_string()).collect();
+        assert_eq!("3.9.9", newest_release(&versions, None).unwrap().to_string());
+        assert_eq!("3.8.8", newest_release(&versions, Some("3.8.*")).unwrap().to_string());
+        assert_eq!("3.9.9", newest_release(&versions, Some("3.9")).unwrap().to_string());
+        assert_eq!("3.0.5", newest_release(&versions, Some("3.0")).unwrap().to_string());
+        assert!(newest_release(&versions, Some("4")).is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! # Maven arguments and JVM options
+//!
+//! Maven's launcher reads them from the project root (`maven.multiModuleProjectDirectory`):
+//! - `.mvn/maven.config` holds arguments that come before those of the command line
+//! - `.mvn/jvm.config` holds JVM options that come before `MAVEN_OPTS`
+//!
+//! The launcher finds these files by itself when started anywhere within the root, which is always the case here,
+//! so javabox only passes what `javabox.toml` adds.
+use std::path::Path;
+
+use crate::config::MavenConfig;
+use crate::config::env::ProjectEnv;
+
+pub const MAVEN_CONFIG: &str = ".mvn/maven.config";
+pub const JVM_CONFIG: &str = ".mvn/jvm.config";
+pub const MAVEN_OPTS: &str = "MAVEN_OPTS";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MavenOptions {
+    /// from `.mvn/maven.config`
+    config_args: Vec<String>,
+    /// from `.mvn/jvm.config`
+    config_jvm_options: Vec<String>,
+    /// from `javabox.toml`
+    args: Vec<String>,
+    /// from `javabox.toml`
+    jvm_options: Vec<String>,
+    /// `MAVEN_OPTS` before adding `jvm_options`
+    maven_opts: Option<String>,
+}
+
+impl MavenOptions {
+    /// Reads the options of the project with given root directory.
+    pub fn load(project_dir: &Path, maven: &MavenConfig, env: &ProjectEnv) -> std::io::Result<Self> {
+        Ok(MavenOptions {
+            config_args: read_config(&project_dir.join(MAVEN_CONFIG))?,
+            config_jvm_options: read_config(&project_dir.join(JVM_CONFIG))?,
+            args: maven.maven_config.clone(),
+            jvm_options: maven.jvm_config.clone(),
+            maven_opts: env.var(MAVEN_OPTS),
+        })
+    }
+
+    /// Arguments that javabox passes before those of the command line
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// New value of `MAVEN_OPTS`, if javabox adds anything to the current one
+    pub fn maven_opts(&self) -> Option<String> {
+        if self.jvm_options.is_empty() {
+            return None;
+        }
+        Some(self.maven_opts.iter().chain(&self.jvm_options).map(String::as_str).collect::<Vec<_>>().join(" "))
+    }
+
+    /// All arguments Maven gets before those of the command line
+    pub fn effective_args(&self) -> Vec<&str> {
+        self.config_args.iter().chain(&self.args).map(String::as_str).collect()
+    }
+
+    /// JVM options as Maven's JVM gets them: `.mvn/jvm.config`, then `MAVEN_OPTS`
+    pub fn effective_jvm_options(&self) -> String {
+        let maven_opts = self.maven_opts().or(self.maven_opts.clone());
+        self.config_jvm_options.iter().map(String::as_str)
+            .chain(maven_opts.as_deref())
+            .filter(|option| !option.trim().is_empty())
+            .collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Splits the file into whitespace-separated tokens; lines starting with `#` are comments.
+fn read_config(path: &Path) -> std::io::Result<Vec<String>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    log::trace!("read_config({})", path.display());
+    Ok(parse_config(&std::fs::read_to_string(path)?))
+}
+
+fn parse_config(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::MavenConfig;
+    use crate::config::env::ProjectEnv;
+    use crate::utils::testing::TempDir;
+
+    use super::{MavenOptions, parse_config, JVM_CONFIG, MAVEN_CONFIG};
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(vec!["-T4", "--errors", "-Dstyle.color=always"],
+                   parse_config("# build faster\n-T4 --errors\r\n\n  -Dstyle.color=always\n"));
+        assert!(parse_config("").is_empty());
+    }
+
+    #[test]
+    fn test_load() {
+        let project_dir = TempDir::new("maven-options");
+        std::fs::create_dir_all(project_dir.join(".mvn")).unwrap();
+        std::fs::write(project_dir.join(MAVEN_CONFIG), "-T4\n").unwrap();
+        std::fs::write(project_dir.join(JVM_CONFIG), "-Xmx2g\n-XX:+UseG1GC\n").unwrap();
+        let maven = MavenConfig {
+            maven_config: vec!["--errors".to_string()],
+            ..Default::default()
+        };
+        let mut env = ProjectEnv::default();
+        env.vars.insert("MAVEN_OPTS".to_string(), "-Dfoo=bar".to_string());
+        let options = MavenOptions::load(&project_dir, &maven, &env).unwrap();
+        assert_eq!(vec!["-T4", "--errors"], options.effective_args());
+        assert_eq!(["--errors"], options.args());
+        assert!(options.maven_opts().is_none());
+        assert_eq!("-Xmx2g -XX:+UseG1GC -Dfoo=bar", options.effective_jvm_options());
+    }
+}
@@ -0,0 +1,285 @@
+//! # Project descriptor
+//!
+//! Only the parts of `pom.xml` that javabox cares about are read; see `doc/mvn-wrapper.md`.
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde_derive::Deserialize;
+
+use crate::config::cache;
+
+const COMPILER_PLUGIN: &str = "maven-compiler-plugin";
+
+/// Properties that projects commonly use to declare Java version, in order of preference
+const JAVA_VERSION_PROPERTIES: [&str; 6] = [
+    "maven.compiler.release",
+    "maven.compiler.source",
+    "maven.compiler.target",
+    "java.version",
+    "jdk.version",
+    "java.release",
+];
+
+/// Guards against parent cycles and absurdly deep hierarchies
+const MAX_PARENT_DEPTH: usize = 32;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename = "project")]
+#[serde(rename_all = "camelCase")]
+pub struct PomXml {
+    pub parent: Option<PomParent>,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+    pub build: Option<PomBuild>,
+    pub modules: Option<PomModules>,
+    pub profiles: Option<PomProfiles>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomParent {
+    pub relative_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PomModules {
+    #[serde(default)]
+    pub module: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PomProfiles {
+    #[serde(default)]
+    pub profile: Vec<PomProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PomProfile {
+    pub modules: Option<PomModules>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomBuild {
+    pub plugins: Option<PomPlugins>,
+    pub plugin_management: Option<PomPluginManagement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PomPluginManagement {
+    pub plugins: Option<PomPlugins>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PomPlugins {
+    #[serde(default)]
+    pub plugin: Vec<PomPlugin>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomPlugin {
+    pub artifact_id: Option<String>,
+    pub configuration: Option<CompilerConfiguration>,
+}
+
+/// The `maven-compiler-plugin` configuration; other plugins' configurations are read into it too, and ignored.
+#[derive(Debug, Deserialize)]
+pub struct CompilerConfiguration {
+    pub release: Option<String>,
+    pub source: Option<String>,
+    pub target: Option<String>,
+}
+
+impl PomXml {
+    pub fn load(pom_file: &Path) -> std::io::Result<Self> {
+        log::trace!("PomXml::load({})", pom_file.display());
+        cache::note_input(pom_file);
+        let file = std::fs::File::open(pom_file)?;
+        serde_xml_rs::from_reader(file)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("Invalid format of {} :: {e:?}", pom_file.display())))
+    }
+
+    /// Location of the parent POM on disk, following Maven rules: `relativePath` defaults to `../pom.xml`,
+    /// and an empty one disables the local lookup.
+    fn parent_pom_file(&self, pom_file: &Path) -> Option<PathBuf> {
+        let parent = self.parent.as_ref()?;
+        let relative_path = parent.relative_path.as_deref().unwrap_or("../pom.xml").trim();
+        if relative_path.is_empty() {
+            return None;
+        }
+        let path = pom_file.parent()?.join(relative_path);
+        let path = if path.is_dir() { path.join("pom.xml") } else { path };
+        if path.is_file() { Some(path) } else { None }
+    }
+
+    /// Modules of the reactor, including those of profiles; any of them may be active
+    fn modules(&self) -> impl Iterator<Item=&String> {
+        let profiles = self.profiles.iter().flat_map(|profiles| profiles.profile.iter());
+        self.modules.iter()
+            .chain(profiles.filter_map(|profile| profile.modules.as_ref()))
+            .flat_map(|modules| modules.module.iter())
+    }
+
+    /// Configuration of compiler plugin, either from `plugins` or `pluginManagement`
+    fn compiler_configuration(&self) -> impl Iterator<Item=&CompilerConfiguration> {
+        let build = self.build.as_ref();
+        let plugins = build.and_then(|b| b.plugins.as_ref());
+        let managed = build.and_then(|b| b.plugin_management.as_ref()).and_then(|pm| pm.plugins.as_ref());
+        plugins.into_iter().chain(managed)
+            .flat_map(|plugins| plugins.plugin.iter())
+            .filter(|plugin| plugin.artifact_id.as_deref() == Some(COMPILER_PLUGIN))
+            .filter_map(|plugin| plugin.configuration.as_ref())
+    }
+}
+
+/// Reads the POM together with its local parents, starting with the given one.
+pub fn load_hierarchy(pom_file: &Path) -> std::io::Result<Vec<PomXml>> {
+    let mut hierarchy = Vec::new();
+    let mut next = Some(pom_file.to_path_buf());
+    while let Some(pom_file) = next {
+        if hierarchy.len() >= MAX_PARENT_DEPTH {
+            log::warn!("Too deep parent hierarchy, stopping at {}", pom_file.display());
+            break;
+        }
+        let pom = match PomXml::load(&pom_file) {
+            Ok(pom) => pom,
+            // the project itself must be readable, its parents not necessarily
+            Err(e) if !hierarchy.is_empty() => {
+                log::warn!("Ignoring unreadable parent POM: {e}");
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        next = pom.parent_pom_file(&pom_file);
+        hierarchy.push(pom);
+    }
+    Ok(hierarchy)
+}
+
+/// Directories of the modules that the POM aggregates; a module is either a directory, or a POM file in it.
+pub fn module_dirs(pom_file: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let pom = PomXml::load(pom_file)?;
+    let base = pom_file.parent().unwrap_or(Path::new("."));
+    Ok(pom.modules()
+        .map(|module| base.join(module.trim()))
+        .map(|path| if path.is_file() { path.parent().map(Path::to_path_buf).unwrap_or(path) } else { path })
+        .collect())
+}
+
+/// Infers the Java version the project compiles for, like `17` or `1.8`.
+/// Looks at compiler plugin configuration and well-known properties, in the POM and its parents.
+pub fn infer_java_version(pom_file: &Path) -> std::io::Result<Option<String>> {
+    let hierarchy = load_hierarchy(pom_file)?;
+    Ok(java_version(&hierarchy))
+}
+
+fn java_version(hierarchy: &[PomXml]) -> Option<String> {
+    // properties are inherited, child definitions win
+    let mut properties = HashMap::new();
+    for pom in hierarchy.iter().rev() {
+        properties.extend(pom.properties.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    }
+    let configured = hierarchy.iter()
+        .flat_map(|pom| pom.compiler_configuration())
+        .flat_map(|conf| [&conf.release, &conf.source, &conf.target])
+        .flatten()
+        .map(String::as_str);
+    let from_properties = JAVA_VERSION_PROPERTIES.iter()
+        .filter_map(|name| properties.get(name).copied());
+    configured.chain(from_properties)
+        .filter_map(|value| resolve_properties(value, &properties))
+        .find(|value| is_java_version(value))
+}
+
+/// Replaces `${name}` references with property values; `None` if some reference cannot be resolved.
+fn resolve_properties(value: &str, properties: &HashMap<&str, &str>) -> Option<String> {
+    let mut value = value.trim().to_string();
+    // limit the rounds, properties can refer to each other in cycles
+    for _ in 0..MAX_PARENT_DEPTH {
+        let start = match value.find("${") {
+            None => return Some(value),
+            Some(start) => start,
+        };
+        let end = start + value[start..].find('}')?;
+        let name = &value[start + 2..end];
+        let replacement = properties.get(name)?.trim();
+        value.replace_range(start..=end, replacement);
+    }
+    None
+}
+
+/// Only accept what looks like a version, like `17`, `1.8` or `21-preview`
+fn is_java_version(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::utils::testing::TempDir;
+
+    use super::infer_java_version;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_infer_java_version() {
+        let tmp = TempDir::new("pom");
+        write(&tmp.join("pom.xml"), r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <groupId>com.example</groupId>
+  <artifactId>parent</artifactId>
+  <properties>
+    <jdk.release>21</jdk.release>
+    <java.version>${jdk.release}</java.version>
+  </properties>
+  <modules>
+    <module>app</module>
+  </modules>
+  <build>
+    <pluginManagement>
+      <plugins>
+        <plugin>
+          <artifactId>maven-compiler-plugin</artifactId>
+          <configuration>
+            <release>${java.version}</release>
+            <compilerArgs><arg>-parameters</arg></compilerArgs>
+          </configuration>
+        </plugin>
+      </plugins>
+    </pluginManagement>
+  </build>
+</project>"#);
+        write(&tmp.join("app/pom.xml"), r#"<project>
+  <parent>
+    <groupId>com.example</groupId>
+    <artifactId>parent</artifactId>
+  </parent>
+  <artifactId>app</artifactId>
+  <build>
+    <plugins>
+      <plugin>
+        <artifactId>maven-surefire-plugin</artifactId>
+        <configuration><skipTests>true</skipTests></configuration>
+      </plugin>
+    </plugins>
+  </build>
+</project>"#);
+        write(&tmp.join("lib/pom.xml"), r#"<project>
+  <parent><relativePath/></parent>
+  <properties><maven.compiler.source>1.8</maven.compiler.source></properties>
+</project>"#);
+        write(&tmp.join("silent/pom.xml"), r#"<project><artifactId>silent</artifactId></project>"#);
+
+        assert_eq!(Some("21".to_string()), infer_java_version(&tmp.join("app/pom.xml")).unwrap());
+        assert_eq!(Some("1.8".to_string()), infer_java_version(&tmp.join("lib/pom.xml")).unwrap());
+        assert_eq!(None, infer_java_version(&tmp.join("silent/pom.xml")).unwrap());
+    }
+}
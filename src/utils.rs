@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs::File;
 use std::io::{BufReader, Error, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::process::{Command, Stdio};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crypto::digest::Digest;
@@ -11,47 +13,76 @@ use indicatif::{ProgressBar, ProgressStyle};
 use ureq::Response;
 use url::Url;
 
-/// Runs the specified tool from project directory with working directory changed to specified module
-pub fn execute_tool(project: &Path, tool: &str, module: &Path) -> std::io::Result<i32> {
+use crate::config::cache;
+use crate::config::env::ProjectEnv;
+use crate::jdk;
+use crate::utils::checksum::Checksum;
+
+/// Runs the specified tool from project directory with working directory changed to specified module.
+/// If `java_home` is given, the tool runs with that JDK; the project environment comes on top of the current one.
+pub fn execute_tool(project: &Path, tool: &str, module: &Path, java_home: Option<&Path>, env: &ProjectEnv, args: &[OsString]) -> std::io::Result<i32> {
     log::info!("Running {tool} for project {} in module {}", project.display(), module.display());
     let mut command = std::process::Command::new(project.join(tool));
     command.current_dir(module);
-    command.args(std::env::args().skip(1));
+    command.args(&env.properties);
+    command.args(args);
+    env.apply(&mut command);
     command.stdout(Stdio::inherit());
     command.stderr(Stdio::inherit());
-    let status = command.status()?;
-    match status.code() {
-        None => Err(std::io::Error::new(ErrorKind::BrokenPipe, "Interrupted")),
-        Some(code) => Ok(code)
+    if let Some(java_home) = java_home {
+        jdk::apply_java_home(&mut command, java_home)?;
+    }
+    run_in_place(&mut command)
+}
+
+/// Runs the command in place of javabox, so that the caller sees signals and exit status of the tool itself.
+/// On Unix, the process is replaced by `exec` and this only returns on failure to start the tool.
+/// Elsewhere, it waits for the tool and returns its exit code; Ctrl-C reaches the whole console there anyway.
+pub fn run_in_place(command: &mut Command) -> std::io::Result<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = command.exec();
+        Err(Error::new(e.kind(), format!("Cannot execute {} :: {e}", Path::new(command.get_program()).display())))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status()?;
+        Ok(status.code().unwrap_or(1))
     }
 }
 
 /// Read property file into a HashMap.
-/// Empty lines and comments are ignored.
-/// Lines in form `KEY=VALUE` are read.
-/// Other lines are reported to stderr.
+/// The complete `java.util.Properties` format is supported, see [properties::Properties].
 pub fn read_properties(properties: &mut HashMap<String,String>, path: &Path) -> std::io::Result<()> {
     log::trace!("read_properties({})", path.display());
-    let text = std::fs::read_to_string(path)?;
-    for line in text.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-        match line.find('=') {
-            None => {
-                log::warn!("ERROR: Bad line: {line}");
-            }
-            Some(n) => {
-                let key = &line[0..n];
-                let value = &line[n + 1..].replace("\\:", ":");
-                properties.insert(key.to_string(), value.to_string());
-            }
-        }
+    cache::note_input(path);
+    let props = properties::Properties::load(path)?;
+    for (key, value) in props.entries() {
+        properties.insert(key.to_string(), value.to_string());
     }
     Ok(())
 }
 
+/// Environment variable that enables offline mode, like `JAVABOX_OFFLINE=1`
+pub const JAVABOX_OFFLINE: &str = "JAVABOX_OFFLINE";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enables offline mode for the rest of this process.
+pub fn set_offline() {
+    log::debug!("Offline mode");
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// In offline mode, javabox never touches the network, and only uses what is already installed.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed) || match std::env::var(JAVABOX_OFFLINE) {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false" | "no"),
+        Err(_) => false,
+    }
+}
+
 /// ureq doesn't have this
 fn get_content_length(response: &Response) -> Option<u64> {
     match response.header("content-length") {
@@ -60,17 +91,48 @@ fn get_content_length(response: &Response) -> Option<u64> {
     }
 }
 
-/// Downloads a file from given URL.
+/// HTTP client; the timeout applies to both connecting and reading, like `networkTimeout` of Gradle wrapper.
+pub fn agent(timeout: Option<Duration>) -> ureq::Agent {
+    match timeout {
+        Some(timeout) => ureq::AgentBuilder::new().timeout_connect(timeout).timeout_read(timeout).build(),
+        None => ureq::agent(),
+    }
+}
+
+/// Downloads a distribution archive, and verifies its checksum.
+/// The checksum is either pinned (typically `distributionSha256Sum`), or the one published next to the distribution.
+pub fn download_distribution(url: &Url, path: &Path, pinned_checksum: Option<&str>, timeout: Option<Duration>) -> std::io::Result<()> {
+    refuse_offline(url, path)?;
+    let checksum = checksum::resolve_checksum(url, pinned_checksum)?;
+    download_checked(url, path, checksum.as_ref(), timeout)
+}
+
+/// Downloads a file, and refuses to keep it if the checksum does not match.
 /// It is safe - the new file exists only if it was read successfully; download is pointed to a different file.
-pub fn download(url: &Url, path: &Path) -> std::io::Result<()> {
-    log::info!("Downloading {} from {}", path.display(), url.as_str());
-    let request = ureq::get(url.as_str());
+/// Local `file:` URLs are copied the same way.
+fn download_checked(url: &Url, path: &Path, checksum: Option<&Checksum>, timeout: Option<Duration>) -> std::io::Result<()> {
+    refuse_offline(url, path)?;
+    if url.scheme() == "file" {
+        let source = url.to_file_path()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Not a local file: {url}")))?;
+        log::info!("Copying {} from {}", path.display(), source.display());
+        let file = File::open(&source)?;
+        let total_size = file.metadata()?.len();
+        return save_response(file, total_size, url, path, checksum);
+    }
+    log::info!("Downloading {} from {}", path.display(), redacted(url));
+    let request = agent(timeout).get(url.as_str());
     let response = request.call()
-        .map_err(|e| std::io::Error::new(ErrorKind::Other, format!("Problem with request: {url} :: {e:?}")))?;
+        .map_err(|e| std::io::Error::new(ErrorKind::Other, format!("Problem with request: {} :: {}", redacted(url), describe(&e))))?;
     if response.status() != 200 {
-        return Err(Error::new(ErrorKind::Other, format!("HTTP Status {}:{} on {}", response.status(), response.status_text(), response.get_url())));
+        return Err(Error::new(ErrorKind::Other, format!("HTTP Status {}:{} on {}", response.status(), response.status_text(), redacted(url))));
     }
     let total_size = get_content_length(&response).ok_or(std::io::Error::new(ErrorKind::Other, "Cannot get content length"))?;
+    save_response(response.into_reader(), total_size, url, path, checksum)
+}
+
+/// Writes the body of a successful response into the file, and refuses to keep it if the checksum does not match.
+fn save_response(body: impl Read, total_size: u64, url: &Url, path: &Path, checksum: Option<&Checksum>) -> std::io::Result<()> {
 
     let mut tmp_path = path.display().to_string();
     if path.exists() {
@@ -89,11 +151,12 @@ pub fn download(url: &Url, path: &Path) -> std::io::Result<()> {
     }
     let tmp_path = PathBuf::from(&tmp_path);
     std::fs::create_dir_all(tmp_path.parent().unwrap())?;
-    let mut br = BufReader::new(response.into_reader());
+    let mut br = BufReader::new(body);
     let mut buf = [0; 8192];
     let mut wr = File::options()
         .create(true)
         .write(true)
+        .truncate(true)
         .open(&tmp_path)?;
     let pb = ProgressBar::new(total_size);
     pb.set_style(ProgressStyle::default_bar()
@@ -101,6 +164,7 @@ pub fn download(url: &Url, path: &Path) -> std::io::Result<()> {
         .map_err(|e| std::io::Error::new(ErrorKind::Other, format!("ERROR: {e:?}")))?
         .progress_chars("#>-"));
     pb.set_message(format!("Downloading {}", path.file_name().unwrap().to_str().unwrap()));
+    let mut digest = checksum.map(|c| c.digest());
     let mut downloaded = 0;
     loop {
         let sz = br.read(&mut buf)?;
@@ -110,30 +174,65 @@ pub fn download(url: &Url, path: &Path) -> std::io::Result<()> {
             wr.flush()?;
             break;
         }
-        wr.write(&buf[0..sz])?;
+        wr.write_all(&buf[0..sz])?;
+        if let Some(digest) = digest.as_mut() {
+            digest.input(&buf[0..sz]);
+        }
     }
     pb.finish_and_clear();
-    // TODO: verify checksum
+    drop(wr);
+    if let (Some(checksum), Some(digest)) = (checksum, digest.as_mut()) {
+        if let Err(e) = checksum.check(digest.as_mut(), redacted(url).as_str()) {
+            log::error!("Refusing to install {}: {e}", path.display());
+            std::fs::remove_file(&tmp_path)?;
+            return Err(e);
+        }
+    }
     // give it the proper name
     std::fs::rename(tmp_path, path)?;
     Ok(())
 }
 
-/// Downloads a file only if it is older than provided age.
+fn refuse_offline(url: &Url, path: &Path) -> std::io::Result<()> {
+    if is_offline() && url.scheme() != "file" {
+        return Err(Error::new(ErrorKind::NotConnected, format!("Offline mode: {} is missing, and cannot be downloaded from {}", path.display(), redacted(url))));
+    }
+    Ok(())
+}
+
+/// The URL without password, for messages; credentials may be part of distribution URLs.
+pub fn redacted(url: &Url) -> Url {
+    let mut url = url.clone();
+    let _ = url.set_password(None);
+    url
+}
+
+/// Describes a failed request without its URL, which may carry credentials
+pub fn describe(e: &ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(status, response) => format!("HTTP Status {status}:{}", response.status_text()),
+        ureq::Error::Transport(transport) => match transport.message() {
+            Some(message) => format!("{}: {message}", transport.kind()),
+            None => transport.kind().to_string(),
+        },
+    }
+}
+
+/// Downloads a file only if it is older than provided age; `Duration::MAX` means never, once the file exists.
 /// The file is reused otherwise, and also if troubles occur during age check or download.
 /// Useful only when being totally up-to-date is not critical.
 pub fn download_or_reuse(url: &Url, path: &Path, max_age: Duration) -> std::io::Result<()> {
     match std::fs::metadata(path) {
+        Ok(_) if is_offline() => {
+            log::debug!("Offline mode, reusing {}", path.display());
+            Ok(())
+        }
         Ok(stat) => {
             let mut needs_update = true;
             match stat.modified().or(stat.created()) {
                 Ok(time) => {
-                    let max_age_secs = max_age.as_secs();
                     // if too fresh, avoid updating
-                    let age_secs = time.elapsed()
-                        .unwrap_or(Duration::from_secs(max_age_secs + 1))
-                        .as_secs();
-                    needs_update = age_secs > max_age_secs;
+                    needs_update = time.elapsed().map(|age| age > max_age).unwrap_or(true);
                 }
                 Err(e) => {
                     log::warn!("Cannot read modification time of '{}', file will be updated. Error is: {e:?}", path.display());
@@ -141,7 +240,7 @@ pub fn download_or_reuse(url: &Url, path: &Path, max_age: Duration) -> std::io::
             }
             if needs_update {
                 // try to update, but don't die if you can't
-                if let Err(e) = download(&url, &path) {
+                if let Err(e) = refresh(url, path) {
                     log::warn!("Failed to update file '{}', let's assume that the latest version didn't change. Error is: {e:?}", path.display());
                 }
             }
@@ -151,11 +250,57 @@ pub fn download_or_reuse(url: &Url, path: &Path, max_age: Duration) -> std::io::
             if path.exists() {
                 log::warn!("Cannot read file information: '{}'. Error is: {e:?}", path.display())
             }
-            download(&url, &path)
+            refresh(url, path)
         }
     }
 }
 
+/// Downloads a file, unless the server confirms that the existing one did not change.
+/// Validators of the last download (`ETag`, `Last-Modified`) are kept next to the file, with `.headers` suffix;
+/// the file's modification time tells when it was last checked.
+pub fn refresh(url: &Url, path: &Path) -> std::io::Result<()> {
+    let headers_path = PathBuf::from(format!("{}.headers", path.display()));
+    if !path.is_file() {
+        refuse_offline(url, path)?;
+    } else if is_offline() {
+        return Err(Error::new(ErrorKind::NotConnected, format!("Offline mode: {} cannot be refreshed from {}", path.display(), redacted(url))));
+    }
+    let mut request = ureq::get(url.as_str());
+    if path.is_file() {
+        let validators = std::fs::read_to_string(&headers_path).unwrap_or_default();
+        for (header, value) in validators.lines().filter_map(|line| line.split_once(": ")) {
+            match header {
+                "ETag" => request = request.set("If-None-Match", value),
+                "Last-Modified" => request = request.set("If-Modified-Since", value),
+                _ => {}
+            }
+        }
+    }
+    log::debug!("Checking {} for updates of {}", redacted(url), path.display());
+    let response = request.call()
+        .map_err(|e| std::io::Error::new(ErrorKind::Other, format!("Problem with request: {} :: {}", redacted(url), describe(&e))))?;
+    match response.status() {
+        304 => {
+            log::debug!("Not modified: {}", path.display());
+            File::options().write(true).open(path)?.set_modified(SystemTime::now())
+        }
+        200 => {
+            let validators: String = ["ETag", "Last-Modified"].iter()
+                .filter_map(|header| response.header(header).map(|value| format!("{header}: {value}\n")))
+                .collect();
+            let total_size = get_content_length(&response).ok_or(std::io::Error::new(ErrorKind::Other, "Cannot get content length"))?;
+            save_response(response.into_reader(), total_size, url, path, None)?;
+            if validators.is_empty() {
+                let _ = std::fs::remove_file(&headers_path);
+                Ok(())
+            } else {
+                std::fs::write(&headers_path, validators)
+            }
+        }
+        status => Err(Error::new(ErrorKind::Other, format!("HTTP Status {status}:{} on {}", response.status_text(), redacted(url)))),
+    }
+}
+
 /// Decides if provided directory is the root of SCM working copy, by examining the presence of metadata.
 /// Returns:
 /// - true if it _surely is_
@@ -188,3 +333,10 @@ mod tests {
         assert_eq!("260hg96vuh6ex27h9vo47iv4d", md5decimal("https://services.gradle.org/distributions/gradle-7.2-all.zip"))
     }
 }
+
+pub mod archive;
+pub mod checksum;
+pub mod properties;
+#[cfg(test)]
+pub mod testing;
+pub mod version;
@@ -0,0 +1,348 @@
+//! # Archive extraction
+//!
+//! Distributions come as `.zip` (Maven, Gradle, Windows JDKs) or as tarballs (Linux and macOS JDKs, some mirrors).
+//! All formats are extracted the same way: unix permissions and symlinks are kept (zip has them in the unix mode
+//! of entries), and the single top-level directory is stripped away.
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
+
+/// File type bits of a unix mode, and the type of symlinks
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+impl ArchiveFormat {
+    /// Determines archive format from the file name.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Determines archive format from the first bytes of its content.
+    pub fn from_magic(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::TarXz)
+        } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Archive file name without the format suffix, like `apache-maven-3.8.6-bin` for `apache-maven-3.8.6-bin.zip`.
+    pub fn base_name(file_name: &str) -> &str {
+        for suffix in [".zip", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar"] {
+            if file_name.len() > suffix.len() && file_name.to_ascii_lowercase().ends_with(suffix) {
+                return &file_name[..file_name.len() - suffix.len()];
+            }
+        }
+        file_name
+    }
+}
+
+/// Extracts an archive into target directory. The format is taken from the file name, or from its magic bytes.
+/// If all entries share a single top-level directory, it is stripped away.
+pub fn extract_archive(archive_path: &Path, target_dir: &Path) -> std::io::Result<()> {
+    let mut file = File::open(archive_path)?;
+    let format = match archive_path.file_name().and_then(|n| n.to_str()).and_then(ArchiveFormat::from_file_name) {
+        Some(format) => format,
+        None => {
+            let mut header = Vec::with_capacity(512);
+            (&mut file).take(512).read_to_end(&mut header)?;
+            file.seek(SeekFrom::Start(0))?;
+            ArchiveFormat::from_magic(&header)
+                .ok_or(std::io::Error::new(ErrorKind::Unsupported, format!("Unknown archive format: {}", archive_path.display())))?
+        }
+    };
+    log::trace!("Extracting {} ({format:?}) to {}", archive_path.display(), target_dir.display());
+    match format {
+        ArchiveFormat::Zip => {
+            extract_zip(file, target_dir)
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("Failed to extract file: {} :: {e}", archive_path.display())))
+        }
+        _ => {
+            // first pass only finds out whether to strip the top-level directory
+            let toplevel = tar_toplevel(format, file)?;
+            let file = File::open(archive_path)?;
+            extract_tar(format, file, target_dir, toplevel.as_deref())
+                .map_err(|e| std::io::Error::new(e.kind(), format!("Failed to extract file: {} :: {e}", archive_path.display())))
+        }
+    }
+}
+
+fn tar_reader(format: ArchiveFormat, file: File) -> tar::Archive<Box<dyn Read>> {
+    let reader = BufReader::new(file);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(reader)),
+        ArchiveFormat::TarXz => Box::new(XzDecoder::new(reader)),
+        _ => Box::new(reader),
+    };
+    tar::Archive::new(reader)
+}
+
+/// Returns the top-level directory name if all entries live under it.
+fn tar_toplevel(format: ArchiveFormat, file: File) -> std::io::Result<Option<PathBuf>> {
+    let mut archive = tar_reader(format, file);
+    let entries = archive.entries()?.map(|entry| {
+        let entry = entry?;
+        Ok((entry.path()?.into_owned(), entry.header().entry_type().is_dir()))
+    });
+    find_toplevel(entries)
+}
+
+/// Returns the top-level directory name if all entries, given by path and whether they are directories, live under it.
+fn find_toplevel(entries: impl Iterator<Item=std::io::Result<(PathBuf, bool)>>) -> std::io::Result<Option<PathBuf>> {
+    let mut toplevel: Option<PathBuf> = None;
+    for entry in entries {
+        let (path, is_dir) = entry?;
+        let mut components = path.components().filter(|c| !matches!(c, Component::CurDir));
+        let first = match components.next() {
+            Some(Component::Normal(first)) => PathBuf::from(first),
+            Some(_) => return Ok(None),
+            None => continue,
+        };
+        let is_dir_itself = components.next().is_none();
+        if is_dir_itself && !is_dir {
+            // a plain file at the top level
+            return Ok(None);
+        }
+        match &toplevel {
+            None => toplevel = Some(first),
+            Some(toplevel) if *toplevel != first => return Ok(None),
+            Some(_) => {}
+        }
+    }
+    Ok(toplevel)
+}
+
+fn extract_tar(format: ArchiveFormat, file: File, target_dir: &Path, toplevel: Option<&Path>) -> std::io::Result<()> {
+    std::fs::create_dir_all(target_dir)?;
+    let mut archive = tar_reader(format, file);
+    archive.set_preserve_permissions(true);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let relative = strip_toplevel(&path, toplevel)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        check_no_symlink(target_dir, &relative)?;
+        let outpath = target_dir.join(&relative);
+        if let Some(parent) = outpath.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if entry.header().entry_type().is_hard_link() {
+            // hard links refer to other entries by their archive path
+            let link_name = entry.link_name()?
+                .ok_or(std::io::Error::new(ErrorKind::InvalidData, format!("Hard link without target: {}", path.display())))?;
+            let link_target = target_dir.join(strip_toplevel(&link_name, toplevel)?);
+            std::fs::hard_link(link_target, &outpath)?;
+            continue;
+        }
+        entry.unpack(&outpath)?;
+    }
+    Ok(())
+}
+
+/// Like [extract_tar]; symlinks are entries with `S_IFLNK` mode, holding the link target as their content.
+fn extract_zip(file: File, target_dir: &Path) -> std::io::Result<()> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))?;
+    let entries = (0..archive.len()).map(|n| {
+        let entry = archive.by_index_raw(n)?;
+        Ok((PathBuf::from(entry.name()), entry.is_dir()))
+    }).collect::<Vec<_>>();
+    let toplevel = find_toplevel(entries.into_iter())?;
+    std::fs::create_dir_all(target_dir)?;
+    for n in 0..archive.len() {
+        let mut entry = archive.by_index(n)?;
+        let relative = strip_toplevel(Path::new(entry.name()), toplevel.as_deref())?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        check_no_symlink(target_dir, &relative)?;
+        let outpath = target_dir.join(&relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&outpath)?;
+            continue;
+        }
+        if let Some(parent) = outpath.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mode = entry.unix_mode();
+        if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+            let mut link_target = String::new();
+            entry.read_to_string(&mut link_target)?;
+            symlink::symlink_auto(link_target, &outpath)?;
+            continue;
+        }
+        std::io::copy(&mut entry, &mut File::create(&outpath)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+    }
+    Ok(())
+}
+
+/// Refuses entries under a symlink extracted earlier, or replacing one; it might point outside the target directory.
+fn check_no_symlink(target_dir: &Path, relative: &Path) -> std::io::Result<()> {
+    let mut path = target_dir.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        if path.symlink_metadata().is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, format!("Archive entry {} goes through symlink {}", relative.display(), path.display())));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the top-level directory from an entry path, and refuses paths that would escape the target directory.
+fn strip_toplevel(path: &Path, toplevel: Option<&Path>) -> std::io::Result<PathBuf> {
+    let path = match toplevel {
+        Some(toplevel) => path.strip_prefix(toplevel).unwrap_or(path),
+        None => path,
+    };
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => result.push(c),
+            Component::CurDir => {}
+            _ => return Err(std::io::Error::new(ErrorKind::InvalidData, format!("Unsafe path in archive: {}", path.display()))),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use zip::write::FileOptions;
+
+    use crate::utils::testing::TempDir;
+
+    use super::{ArchiveFormat, extract_archive};
+
+    fn make_tar_gz(path: &Path) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let content = b"#!/bin/sh\necho hello\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, "jdk-17.0.8.1+1/bin/java", &content[..]).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_mode(0o777);
+        header.set_cksum();
+        builder.append_link(&mut header, "jdk-17.0.8.1+1/bin/java-link", "java").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(Some(ArchiveFormat::Zip), ArchiveFormat::from_file_name("apache-maven-3.8.6-bin.zip"));
+        assert_eq!(Some(ArchiveFormat::TarGz), ArchiveFormat::from_file_name("OpenJDK17U-jdk_x64_linux_hotspot_17.0.8.1_1.tar.gz"));
+        assert_eq!(Some(ArchiveFormat::TarXz), ArchiveFormat::from_file_name("jdk.tar.xz"));
+        assert_eq!(None, ArchiveFormat::from_file_name("jdk.download"));
+        assert_eq!(Some(ArchiveFormat::TarGz), ArchiveFormat::from_magic(&[0x1f, 0x8b, 8, 0]));
+        assert_eq!(Some(ArchiveFormat::Zip), ArchiveFormat::from_magic(b"PK\x03\x04...."));
+        assert_eq!("apache-maven-3.8.6-bin", ArchiveFormat::base_name("apache-maven-3.8.6-bin.zip"));
+        assert_eq!("OpenJDK17U-jdk_x64_linux_hotspot_17.0.8.1_1", ArchiveFormat::base_name("OpenJDK17U-jdk_x64_linux_hotspot_17.0.8.1_1.tar.gz"));
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let tmp = TempDir::new("archive");
+        // no suffix, to exercise the magic bytes detection
+        let archive = tmp.join("jdk.download");
+        make_tar_gz(&archive);
+        let target = tmp.join("jdk");
+        extract_archive(&archive, &target).unwrap();
+        let java = target.join("bin/java");
+        assert!(java.is_file());
+        assert_eq!(Path::new("java"), std::fs::read_link(target.join("bin/java-link")).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&java).unwrap().permissions().mode();
+            assert_eq!(0o755, mode & 0o777);
+        }
+    }
+
+    #[test]
+    fn test_extract_through_symlink() {
+        let tmp = TempDir::new("archive-symlink");
+        let outside = tmp.join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        let archive = tmp.join("evil.tar.gz");
+        let file = std::fs::File::create(&archive).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_mode(0o777);
+        builder.append_link(&mut header, "jdk/lib", &outside).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "jdk/lib/pwned", &b"pwned"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        assert!(extract_archive(&archive, &tmp.join("target")).is_err());
+        assert!(!outside.join("pwned").exists());
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let tmp = TempDir::new("archive-zip");
+        let archive = tmp.join("apache-maven-3.9.6-bin.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        zip.add_directory("apache-maven-3.9.6/bin/", Default::default()).unwrap();
+        zip.start_file("apache-maven-3.9.6/bin/mvn", FileOptions::default().unix_permissions(0o755)).unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        zip.add_symlink("apache-maven-3.9.6/bin/mvn-link", "mvn", Default::default()).unwrap();
+        zip.finish().unwrap();
+        let target = tmp.join("maven");
+        extract_archive(&archive, &target).unwrap();
+        let mvn = target.join("bin/mvn");
+        assert!(mvn.is_file());
+        assert_eq!(Path::new("mvn"), std::fs::read_link(target.join("bin/mvn-link")).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&mvn).unwrap().permissions().mode();
+            assert_eq!(0o755, mode & 0o777);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! # Checksums of downloaded distributions
+//!
+//! The expected checksum is either pinned by configuration (like `distributionSha256Sum` in wrapper properties),
+//! or published next to the distribution file (`.sha256`, `.sha512`, `.sha256.txt`).
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+use crypto::digest::Digest;
+use crypto::sha2::{Sha256, Sha512};
+use url::Url;
+
+use crate::config::global::{self, HashCheck};
+use crate::utils;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Checksum {
+    Sha256(String),
+    Sha512(String),
+}
+
+/// Suffixes of checksum files published next to distributions:
+/// - Maven Central has `.sha512` (and `.sha256` for some artifacts)
+/// - services.gradle.org has `.sha256`
+/// - Adoptium (`checksum_link`) has `.sha256.txt`
+const PUBLISHED_CHECKSUM_SUFFIXES: [&str; 3] = [".sha256", ".sha512", ".sha256.txt"];
+
+impl Checksum {
+    /// Recognizes the algorithm by the length of hex digest.
+    /// Tolerates the `sha256sum` output format, ie. the digest followed by file name.
+    pub fn from_hex(text: &str) -> Option<Self> {
+        let hex = text.split_whitespace().next()?.to_ascii_lowercase();
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match hex.len() {
+            64 => Some(Self::Sha256(hex)),
+            128 => Some(Self::Sha512(hex)),
+            _ => None
+        }
+    }
+
+    pub fn expected(&self) -> &str {
+        match self {
+            Checksum::Sha256(hex) => hex,
+            Checksum::Sha512(hex) => hex,
+        }
+    }
+
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Sha256(_) => "SHA-256",
+            Checksum::Sha512(_) => "SHA-512",
+        }
+    }
+
+    /// Creates a fresh digest for the algorithm of this checksum.
+    pub fn digest(&self) -> Box<dyn Digest> {
+        match self {
+            Checksum::Sha256(_) => Box::new(Sha256::new()),
+            Checksum::Sha512(_) => Box::new(Sha512::new()),
+        }
+    }
+
+    /// Compares computed digest with the expected value.
+    pub fn check(&self, digest: &mut dyn Digest, what: &str) -> std::io::Result<()> {
+        let actual = digest.result_str();
+        if actual != self.expected() {
+            return Err(std::io::Error::new(ErrorKind::InvalidData,
+                format!("{} checksum mismatch for {what}: expected {}, got {actual}", self.algorithm(), self.expected())));
+        }
+        log::debug!("{} checksum verified for {what}", self.algorithm());
+        Ok(())
+    }
+}
+
+/// Determines the checksum to verify a distribution download against.
+/// The pinned value wins; otherwise the published checksum file is looked up.
+/// Hash checking configured by the user may skip verification, or refuse downloads that cannot be verified.
+pub fn resolve_checksum(url: &Url, pinned: Option<&str>) -> std::io::Result<Option<Checksum>> {
+    let (hash_check, setting) = global::hash_check()?;
+    if hash_check == HashCheck::Skip {
+        log::warn!("Hash checking is disabled by {}, {} will not be verified", setting.origin, utils::redacted(url));
+        return Ok(None);
+    }
+    if let Some(pinned) = pinned {
+        let checksum = Checksum::from_hex(pinned)
+            .ok_or(std::io::Error::new(ErrorKind::InvalidInput, format!("Invalid pinned checksum for {}: '{pinned}'", utils::redacted(url))))?;
+        return Ok(Some(checksum));
+    }
+    let checksum = fetch_published_checksum(url);
+    match checksum {
+        None if hash_check == HashCheck::Enforce => {
+            return Err(std::io::Error::new(ErrorKind::InvalidData,
+                format!("No checksum found for {}, and hash checking is enforced by {}", utils::redacted(url), setting.origin)));
+        }
+        None => log::warn!("No published checksum found for {}, the download cannot be verified", utils::redacted(url)),
+        Some(_) => {}
+    }
+    Ok(checksum)
+}
+
+/// Computes SHA-256 of a file, as lowercase hex
+pub fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut digest = Sha256::new();
+    let mut buf = [0; 8192];
+    loop {
+        let sz = file.read(&mut buf)?;
+        if sz == 0 {
+            break;
+        }
+        digest.input(&buf[0..sz]);
+    }
+    Ok(digest.result_str())
+}
+
+/// Tries well-known checksum file suffixes next to the distribution URL.
+fn fetch_published_checksum(url: &Url) -> Option<Checksum> {
+    for suffix in PUBLISHED_CHECKSUM_SUFFIXES {
+        let checksum_url = format!("{url}{suffix}");
+        // for messages only, the request needs the credentials
+        let shown_url = format!("{}{suffix}", utils::redacted(url));
+        log::trace!("Looking for checksum at {shown_url}");
+        let response = match ureq::get(&checksum_url).call() {
+            Ok(response) if response.status() == 200 => response,
+            Ok(response) => {
+                log::trace!("HTTP Status {} on {shown_url}", response.status());
+                continue;
+            }
+            Err(e) => {
+                log::trace!("Checksum not available: {shown_url} :: {}", utils::describe(&e));
+                continue;
+            }
+        };
+        let text = match response.into_string() {
+            Ok(text) => text,
+            Err(e) => {
+                log::warn!("Failed to read checksum from {shown_url} :: {e:?}");
+                continue;
+            }
+        };
+        match Checksum::from_hex(&text) {
+            Some(checksum) => {
+                log::debug!("Using published checksum {shown_url}");
+                return Some(checksum);
+            }
+            None => log::warn!("Ignoring unrecognized checksum content at {shown_url}"),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checksum;
+
+    #[test]
+    fn test_from_hex() {
+        let sha256 = "591855B517FC635B9E04DE1D05D5E76ADA3F89F5FC76F87978D1B245B4F69225";
+        assert_eq!(Some(Checksum::Sha256(sha256.to_ascii_lowercase())), Checksum::from_hex(sha256));
+        assert_eq!(Some(Checksum::Sha256(sha256.to_ascii_lowercase())),
+                   Checksum::from_hex(&format!("{sha256}  OpenJDK17U-jdk_x64_linux_hotspot_17.0.8.1_1.tar.gz\n")));
+        assert!(matches!(Checksum::from_hex(&"ab".repeat(64)), Some(Checksum::Sha512(_))));
+        assert_eq!(None, Checksum::from_hex("not-a-checksum"));
+        assert_eq!(None, Checksum::from_hex("abcd"));
+    }
+
+    #[test]
+    fn test_check() {
+        let checksum = Checksum::Sha256("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string());
+        let mut digest = checksum.digest();
+        digest.input(b"hello");
+        assert!(checksum.check(digest.as_mut(), "hello").is_ok());
+        let mut digest = checksum.digest();
+        digest.input(b"hello, truncated");
+        assert!(checksum.check(digest.as_mut(), "hello").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! # Java properties files
+//!
+//! Implements the format of `java.util.Properties::load(InputStream)`: `=`, `:` or whitespace separators,
+//! `#` and `!` comments, backslash line continuations and escapes including `\uXXXX`.
+//! Like there, files are read as ISO-8859-1, and lines end with `\n`, `\r` or `\r\n`.
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Properties {
+    /// key and value of each entry, in file order
+    entries: Vec<(String, String)>,
+}
+
+impl Properties {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        log::trace!("Properties::load({})", path.display());
+        // ISO-8859-1 maps each byte to the char of the same code
+        let text: String = std::fs::read(path)?.into_iter().map(char::from).collect();
+        Ok(Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut natural_lines = natural_lines(text);
+        while let Some(line) = natural_lines.next() {
+            let trimmed = line.trim_start_matches([' ', '\t', '\x0c']);
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                continue;
+            }
+            // join continuation lines; leading whitespace of each continuation is skipped
+            let mut logical = trimmed.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match natural_lines.next() {
+                    None => break,
+                    Some(next) => logical.push_str(next.trim_start_matches([' ', '\t', '\x0c'])),
+                }
+            }
+            entries.push(split_entry(&logical));
+        }
+        Properties { entries }
+    }
+
+    /// Value of given key; like in Java, the last occurrence wins.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, v)| v)
+    }
+
+    /// Entries in file order
+    pub fn entries(&self) -> impl Iterator<Item=(&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Splits at `\n`, `\r` or `\r\n`
+fn natural_lines(text: &str) -> impl Iterator<Item=&str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+        let line = &rest[..end];
+        rest = &rest[end..];
+        rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix(['\r', '\n'])).unwrap_or(rest);
+        Some(line)
+    })
+}
+
+/// Odd number of trailing backslashes means continuation
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+/// Splits logical line into unescaped key and value.
+fn split_entry(line: &str) -> (String, String) {
+    let mut key_end = line.len();
+    let mut value_start = line.len();
+    let mut escaped = false;
+    for (n, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' => {
+                key_end = n;
+                value_start = n + 1;
+                break;
+            }
+            ' ' | '\t' | '\x0c' => {
+                key_end = n;
+                // whitespace separator may be followed by a single `=` or `:`
+                let rest = line[n..].trim_start_matches([' ', '\t', '\x0c']);
+                let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+                value_start = line.len() - rest.len();
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = line[value_start..].trim_start_matches([' ', '\t', '\x0c']);
+    (unescape(&line[..key_end]), unescape(value))
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            None => {}
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\x0c'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let unit = u32::from_str_radix(&hex, 16).ok();
+                // characters beyond the BMP come as a surrogate pair, like `\uD83D\uDE00`
+                let low = unit.filter(|unit| (0xD800..0xDC00).contains(unit)).and_then(|_| {
+                    let mut rest = chars.clone();
+                    let low = match (rest.next(), rest.next()) {
+                        (Some('\\'), Some('u')) => u32::from_str_radix(&rest.by_ref().take(4).collect::<String>(), 16).ok(),
+                        _ => None,
+                    };
+                    low.filter(|low| (0xDC00..0xE000).contains(low)).map(|low| (low, rest))
+                });
+                let ch = match (unit, low) {
+                    (Some(high), Some((low, rest))) => {
+                        chars = rest;
+                        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                    }
+                    (unit, _) => unit.and_then(char::from_u32),
+                };
+                match ch {
+                    Some(ch) => result.push(ch),
+                    None => {
+                        log::warn!("Malformed \\uXXXX encoding: \\u{hex}");
+                        result.push_str(&hex);
+                    }
+                }
+            }
+            Some(other) => result.push(other),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::testing::TempDir;
+
+    use super::Properties;
+
+    const GRADLE_WRAPPER_PROPERTIES: &str = r#"#Tue Oct 17 10:00:00 CEST 2023
+distributionBase=GRADLE_USER_HOME
+distributionPath=wrapper/dists
+distributionUrl=https\://services.gradle.org/distributions/gradle-8.5-bin.zip
+networkTimeout=10000
+! legacy comment
+zipStoreBase : GRADLE_USER_HOME
+zipStorePath   wrapper/dists
+   indented.key = value with trailing continuation \
+                  on the next line
+unicode=\u017Elu\u0165ou\u010Dk\u00FD k\u016F\u0148
+key\ with\ spaces=a\=b
+empty=
+"#;
+
+    #[test]
+    fn test_parse() {
+        let props = Properties::parse(GRADLE_WRAPPER_PROPERTIES);
+        assert_eq!(Some("https://services.gradle.org/distributions/gradle-8.5-bin.zip"), props.get("distributionUrl"));
+        assert_eq!(Some("GRADLE_USER_HOME"), props.get("zipStoreBase"));
+        assert_eq!(Some("wrapper/dists"), props.get("zipStorePath"));
+        assert_eq!(Some("value with trailing continuation on the next line"), props.get("indented.key"));
+        assert_eq!(Some("žluťoučký kůň"), props.get("unicode"));
+        assert_eq!(Some("a=b"), props.get("key with spaces"));
+        assert_eq!(Some(""), props.get("empty"));
+        assert_eq!(None, props.get("legacy"));
+        assert_eq!(10, props.entries().count());
+    }
+
+    #[test]
+    fn test_continuation_edge_cases() {
+        let props = Properties::parse("a=ends with backslash\\\\\nb=x\\\n\\\n  y\nc\n");
+        assert_eq!(Some("ends with backslash\\"), props.get("a"));
+        assert_eq!(Some("xy"), props.get("b"));
+        assert_eq!(Some(""), props.get("c"));
+    }
+
+    #[test]
+    fn test_surrogate_pairs() {
+        assert_eq!(Some("smile \u{1F600}"), Properties::parse("emoji=smile \\uD83D\\uDE00\n").get("emoji"));
+        // a lone surrogate stays as it was
+        assert_eq!(Some("D83Dx"), Properties::parse("lone=\\uD83Dx").get("lone"));
+    }
+
+    #[test]
+    fn test_line_ends() {
+        let props = Properties::parse("a=1\rb=2\r\nc=x\\\r\n  y\n\rd=4");
+        assert_eq!(vec![("a", "1"), ("b", "2"), ("c", "xy"), ("d", "4")], props.entries().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_load_latin1() {
+        let tmp = TempDir::new("properties");
+        let path = tmp.join("gradle.properties");
+        std::fs::write(&path, b"name=caf\xe9\n").unwrap();
+        assert_eq!(Some("caf\u{e9}"), Properties::load(&path).unwrap().get("name"));
+    }
+}
@@ -0,0 +1,35 @@
+//! Fixtures shared by tests
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Empty directory under the system temp dir, removed with its content when dropped, also when a test panics
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates a directory named after given prefix, the process and a counter, so that no two tests share one.
+    pub fn new(prefix: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("javabox-test-{prefix}-{}-{id}", std::process::id()));
+        // left over by an earlier process with the same id
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
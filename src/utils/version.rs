@@ -0,0 +1,450 @@
+//! # Maven version ordering
+//!
+//! Port of Maven's `org.apache.maven.artifact.versioning.ComparableVersion`, see
+//! [Version Order Specification](https://maven.apache.org/pom.html#version-order-specification).
+//! Versions split into numbers and qualifiers at `.`, `-` and digit/letter transitions; qualifiers order as
+//! `alpha` < `beta` < `milestone` < `rc` < `snapshot` < release (`ga`, `final`) < `sp` < anything else.
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+
+/// Known qualifiers in their order; the empty one stands for release
+const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+const RELEASE_INDEX: &str = "5";
+
+#[derive(Debug, Clone)]
+pub struct ComparableVersion {
+    value: String,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    /// digits without leading zeros
+    Int(String),
+    /// qualifier, with aliases like `cr` or `final` already resolved
+    Str(String),
+    /// qualifier followed by a number, like `alpha-1` or `m2`
+    Combination(String, Box<Item>),
+    List(Vec<Item>),
+}
+
+impl ComparableVersion {
+    pub fn new(version: &str) -> Self {
+        ComparableVersion {
+            value: version.to_string(),
+            items: parse(&version.to_lowercase()),
+        }
+    }
+
+    /// Release, as opposed to alpha, beta, milestone, release candidate or snapshot, which all have qualifiers
+    pub fn is_release(&self) -> bool {
+        fn numeric(items: &[Item]) -> bool {
+            items.iter().all(|item| match item {
+                Item::Int(_) => true,
+                Item::List(items) => numeric(items),
+                Item::Str(_) | Item::Combination(..) => false,
+            })
+        }
+        numeric(&self.items)
+    }
+
+    /// Decides if the version belongs to a line like `3.9`, `3.8.*` or `3`.
+    pub fn is_in_line(&self, line: &str) -> bool {
+        let line = line.trim().trim_end_matches(".*").to_lowercase();
+        let value = self.value.to_lowercase();
+        match value.strip_prefix(&line) {
+            Some(rest) => rest.is_empty() || rest.starts_with(['.', '-']),
+            None => false,
+        }
+    }
+}
+
+impl Display for ComparableVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl Ord for ComparableVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_lists(&self.items, &other.items)
+    }
+}
+
+impl PartialOrd for ComparableVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Like in Maven, `1.0` equals `1` and `1-ga`
+impl PartialEq for ComparableVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ComparableVersion {}
+
+/// Requirement on a version, like `>=17, <22`, `3.9.*` or `~8.5`; all comma-separated parts must hold.
+///
+/// - `>=`, `>`, `<=`, `<`, `=` compare versions
+/// - `~8.5` allows patch updates (`>=8.5, <8.6`), `~8` minor ones (`>=8, <9`)
+/// - `^3.8` allows anything up to the next major version (`>=3.8, <4`)
+/// - `3.9.*` or plain `3.9` mean the line, ie. `3.9` and `3.9.x`
+///
+/// Pre-releases only match when some part of the constraint itself is a pre-release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionConstraint {
+    text: String,
+    parts: Vec<(Operator, ComparableVersion)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    Line,
+}
+
+impl VersionConstraint {
+    /// Decides if the text is a constraint rather than a plain version
+    pub fn is_constraint(text: &str) -> bool {
+        text.contains(['<', '>', '=', '~', '^', '*', ','])
+    }
+
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        let invalid = |reason: &str| std::io::Error::new(ErrorKind::InvalidInput, format!("Invalid version constraint '{text}': {reason}"));
+        let mut parts = Vec::new();
+        for part in text.split(',').map(str::trim) {
+            let (operator, version) = [(">=", Operator::GreaterOrEqual), ("<=", Operator::LessOrEqual), (">", Operator::Greater),
+                ("<", Operator::Less), ("=", Operator::Equal), ("~", Operator::Line), ("^", Operator::Line)].iter()
+                .find_map(|(prefix, operator)| part.strip_prefix(prefix).map(|version| (*operator, version.trim())))
+                .unwrap_or((Operator::Line, part.trim_end_matches(".*").trim_end_matches('*')));
+            if operator != Operator::Line && version.is_empty() {
+                return Err(invalid("missing version"));
+            }
+            if version.contains(|c: char| c.is_whitespace() || "<>=~^*".contains(c)) {
+                return Err(invalid(&format!("unexpected '{version}'")));
+            }
+            match part.chars().next() {
+                // `~8.5` is `>=8.5, <8.6`, `^3.8` is `>=3.8, <4`
+                Some(c @ ('~' | '^')) => {
+                    let numbers: Vec<u64> = version.split('.').map_while(|n| n.parse().ok()).collect();
+                    let bumped = if c == '~' && numbers.len() > 1 { 1 } else { 0 };
+                    let mut upper: Vec<u64> = numbers.iter().take(bumped + 1).copied().collect();
+                    match upper.last_mut() {
+                        None => return Err(invalid(&format!("'{version}' does not start with a number"))),
+                        Some(last) => *last += 1,
+                    }
+                    let upper: Vec<String> = upper.iter().map(u64::to_string).collect();
+                    parts.push((Operator::GreaterOrEqual, ComparableVersion::new(version)));
+                    parts.push((Operator::Less, ComparableVersion::new(&upper.join("."))));
+                }
+                _ => parts.push((operator, ComparableVersion::new(version))),
+            }
+        }
+        Ok(VersionConstraint { text: text.trim().to_string(), parts })
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        let version = ComparableVersion::new(version);
+        if !version.is_release() && self.parts.iter().all(|(_, v)| v.is_release()) {
+            return false;
+        }
+        self.parts.iter().all(|(operator, bound)| match operator {
+            Operator::Greater => version > *bound,
+            Operator::GreaterOrEqual => version >= *bound,
+            Operator::Less => version < *bound,
+            Operator::LessOrEqual => version <= *bound,
+            Operator::Equal => version == *bound,
+            Operator::Line => version.is_in_line(&bound.value),
+        })
+    }
+
+    /// The highest of the candidates that satisfies the constraint
+    pub fn best<'a>(&self, candidates: impl IntoIterator<Item=&'a str>) -> Option<&'a str> {
+        candidates.into_iter()
+            .filter(|candidate| self.matches(candidate))
+            .max_by_key(|candidate| ComparableVersion::new(candidate))
+    }
+
+    /// Error for the case that no candidate satisfies the constraint; lists them, highest first
+    pub fn unsatisfied(&self, tool: &str, candidates: &[String]) -> std::io::Error {
+        let mut candidates: Vec<ComparableVersion> = candidates.iter().map(|c| ComparableVersion::new(c)).collect();
+        candidates.sort();
+        candidates.dedup();
+        let candidates: Vec<String> = candidates.iter().rev().map(ComparableVersion::to_string).collect();
+        let candidates = if candidates.is_empty() { "none".to_string() } else { candidates.join(", ") };
+        std::io::Error::new(ErrorKind::NotFound, format!("No {tool} version satisfies '{}'; considered: {candidates}", self.text))
+    }
+}
+
+impl Display for VersionConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Builds the item tree; each `-`, and each transition between letters and digits, starts a nested list.
+fn parse(version: &str) -> Vec<Item> {
+    // stack of lists being built; each one ends up as the last item of its predecessor
+    let mut stack: Vec<Vec<Item>> = vec![Vec::new()];
+    let chars: Vec<char> = version.chars().collect();
+    let mut is_digit = false;
+    let mut is_combination = false;
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let list_is_empty = stack.last().unwrap().is_empty();
+        match c {
+            '.' => {
+                let item = if i == start { Item::Int("0".to_string()) } else { parse_item(is_combination, is_digit, &chars[start..i]) };
+                stack.last_mut().unwrap().push(item);
+                is_combination = false;
+                start = i + 1;
+            }
+            '-' => {
+                if i == start {
+                    stack.last_mut().unwrap().push(Item::Int("0".to_string()));
+                } else {
+                    // `X-1` is treated as `X1`
+                    if !is_digit && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                        is_combination = true;
+                        i += 1;
+                        continue;
+                    }
+                    stack.last_mut().unwrap().push(parse_item(is_combination, is_digit, &chars[start..i]));
+                }
+                start = i + 1;
+                if !stack.last().unwrap().is_empty() {
+                    stack.push(Vec::new());
+                }
+                is_combination = false;
+            }
+            c if c.is_ascii_digit() => {
+                if !is_digit && i > start {
+                    // `X1`
+                    is_combination = true;
+                    if !list_is_empty {
+                        stack.push(Vec::new());
+                    }
+                }
+                is_digit = true;
+            }
+            _ => {
+                if is_digit && i > start {
+                    stack.last_mut().unwrap().push(parse_item(is_combination, true, &chars[start..i]));
+                    start = i;
+                    stack.push(Vec::new());
+                    is_combination = false;
+                }
+                is_digit = false;
+            }
+        }
+        i += 1;
+    }
+    if chars.len() > start {
+        // `1.0.0.X1` < `1.0.0-X2`: treat `.X` as `-X` for any string qualifier
+        if !is_digit && !stack.last().unwrap().is_empty() {
+            stack.push(Vec::new());
+        }
+        stack.last_mut().unwrap().push(parse_item(is_combination, is_digit, &chars[start..]));
+    }
+    // fold nested lists back, normalizing each
+    let mut list = stack.pop().unwrap();
+    normalize(&mut list);
+    while let Some(mut parent) = stack.pop() {
+        parent.push(Item::List(list));
+        normalize(&mut parent);
+        list = parent;
+    }
+    list
+}
+
+fn parse_item(is_combination: bool, is_digit: bool, chars: &[char]) -> Item {
+    let text: String = chars.iter().filter(|&&c| c != '-').collect();
+    if is_combination {
+        let n = text.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+        let digits: Vec<char> = text[n..].chars().collect();
+        Item::Combination(qualifier(&text[..n], true), Box::new(parse_item(false, true, &digits)))
+    } else if is_digit {
+        let digits = text.trim_start_matches('0');
+        Item::Int(if digits.is_empty() { "0".to_string() } else { digits.to_string() })
+    } else {
+        Item::Str(qualifier(&text, false))
+    }
+}
+
+/// Resolves aliases; single letters mean `alpha`, `beta` and `milestone` only when followed by a number
+fn qualifier(value: &str, followed_by_digit: bool) -> String {
+    let value = match value {
+        "a" if followed_by_digit => "alpha",
+        "b" if followed_by_digit => "beta",
+        "m" if followed_by_digit => "milestone",
+        "ga" | "final" | "release" => "",
+        "cr" => "rc",
+        value => value,
+    };
+    value.to_string()
+}
+
+/// Removes trailing null items (`0`, empty qualifier, empty list), but not across nested lists
+fn normalize(items: &mut Vec<Item>) {
+    let mut i = items.len();
+    while i > 0 {
+        i -= 1;
+        if items[i].is_null() {
+            items.remove(i);
+        } else if !matches!(items[i], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+/// Sortable form of a qualifier; unknown qualifiers come after the known ones, alphabetically
+fn comparable_qualifier(qualifier: &str) -> String {
+    match QUALIFIERS.iter().position(|q| *q == qualifier) {
+        Some(n) => n.to_string(),
+        None => format!("{}-{qualifier}", QUALIFIERS.len()),
+    }
+}
+
+impl Item {
+    fn is_null(&self) -> bool {
+        match self {
+            Item::Int(digits) => digits == "0",
+            Item::Str(value) => value.is_empty(),
+            Item::Combination(..) => false,
+            Item::List(items) => items.is_empty(),
+        }
+    }
+
+    /// Compares with another item, or with nothing (padding of the shorter list)
+    fn compare(&self, other: Option<&Item>) -> Ordering {
+        use Item::*;
+        let other = match other {
+            None => return match self {
+                Int(_) => if self.is_null() { Ordering::Equal } else { Ordering::Greater },
+                Str(value) => comparable_qualifier(value).as_str().cmp(RELEASE_INDEX),
+                Combination(value, _) => comparable_qualifier(value).as_str().cmp(RELEASE_INDEX),
+                List(items) => match items.first() {
+                    None => Ordering::Equal,
+                    Some(first) => first.compare(None),
+                },
+            },
+            Some(other) => other,
+        };
+        match (self, other) {
+            (Int(a), Int(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+            (Int(_), _) => Ordering::Greater,
+            (Str(_), Int(_)) => Ordering::Less,
+            (Str(a), Str(b)) => comparable_qualifier(a).cmp(&comparable_qualifier(b)),
+            // `X` < `X1`
+            (Str(a), Combination(b, _)) => comparable_qualifier(a).cmp(&comparable_qualifier(b)).then(Ordering::Less),
+            (Str(_), List(_)) => Ordering::Less,
+            (Combination(a, _), Str(b)) => comparable_qualifier(a).cmp(&comparable_qualifier(b)).then(Ordering::Greater),
+            (Combination(a, x), Combination(b, y)) => comparable_qualifier(a).cmp(&comparable_qualifier(b)).then_with(|| x.compare(Some(y))),
+            (Combination(..), _) => Ordering::Less,
+            (List(_), Int(_)) => Ordering::Less,
+            (List(_), Str(_) | Combination(..)) => Ordering::Greater,
+            (List(a), List(b)) => compare_lists(a, b),
+        }
+    }
+}
+
+fn compare_lists(a: &[Item], b: &[Item]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let result = match (a.get(i), b.get(i)) {
+            (Some(left), right) => left.compare(right),
+            (None, Some(right)) => right.compare(None).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if result != Ordering::Equal {
+            return result;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComparableVersion, VersionConstraint};
+
+    fn assert_increasing(versions: &[&str]) {
+        for (i, low) in versions.iter().enumerate() {
+            for high in &versions[i + 1..] {
+                assert!(ComparableVersion::new(low) < ComparableVersion::new(high), "{low} < {high}");
+                assert!(ComparableVersion::new(high) > ComparableVersion::new(low), "{high} > {low}");
+            }
+        }
+    }
+
+    /// Sequences from Maven's `ComparableVersionTest`
+    #[test]
+    fn test_ordering() {
+        assert_increasing(&["1-alpha2snapshot", "1-alpha2", "1-alpha-123", "1-beta-2", "1-beta123", "1-m2", "1-m11",
+            "1-rc", "1-cr2", "1-rc123", "1-SNAPSHOT", "1", "1-sp", "1-sp2", "1-sp123", "1-abc", "1-def", "1-pom-1",
+            "1-1-snapshot", "1-1", "1-2", "1-123"]);
+        assert_increasing(&["2.0", "2.0.a", "2-1", "2.0.2", "2.0.123", "2.1.0", "2.1-a", "2.1b", "2.1-c", "2.1-1",
+            "2.1.0.1", "2.2", "2.123", "11.a2", "11.a11", "11.b2", "11.b11", "11.m2", "11.m11", "11", "11.a", "11b",
+            "11c", "11m"]);
+        assert_increasing(&["3.0-alpha-1", "3.0-beta-3", "3.0", "3.0.5", "3.8.8", "3.9.0", "3.9.6", "3.10.0",
+            "4.0.0-alpha-8", "4.0.0-beta-3", "4.0.0-rc-1", "4.0.0"]);
+    }
+
+    #[test]
+    fn test_equality() {
+        for equal in [["1", "1.0"], ["1", "1.0.0"], ["1-ga", "1"], ["1.0-final", "1"], ["1-RELEASE", "1"], ["1a1", "1-alpha-1"],
+            ["1b2", "1-beta-2"], ["1m3", "1-milestone-3"], ["1-cr1", "1-rc-1"], ["1.0.0-SNAPSHOT", "1-snapshot"], ["01.2", "1.002"]] {
+            assert_eq!(ComparableVersion::new(equal[0]), ComparableVersion::new(equal[1]), "{} == {}", equal[0], equal[1]);
+        }
+    }
+
+    #[test]
+    fn test_release_and_line() {
+        assert!(ComparableVersion::new("3.9.6").is_release());
+        assert!(ComparableVersion::new("3.0-final").is_release());
+        for prerelease in ["4.0.0-alpha-8", "4.0.0-rc-1", "3.0-beta-3", "3.0-M1", "3.9.7-SNAPSHOT", "2.0.10a"] {
+            assert!(!ComparableVersion::new(prerelease).is_release(), "{prerelease}");
+        }
+        let version = ComparableVersion::new("3.9.6");
+        assert!(version.is_in_line("3.9"));
+        assert!(version.is_in_line("3.9.*"));
+        assert!(version.is_in_line("3"));
+        assert!(!version.is_in_line("3.8.*"));
+        assert!(!ComparableVersion::new("3.90.1").is_in_line("3.9"));
+    }
+
+    #[test]
+    fn test_constraint() {
+        let candidates = ["8.4", "8.5", "8.5.1", "8.6-rc-1", "8.6", "8.10.2", "7.6.4"];
+        let best = |constraint: &str| VersionConstraint::parse(constraint).unwrap().best(candidates);
+        assert_eq!(Some("8.5.1"), best("~8.5"));
+        assert_eq!(Some("8.10.2"), best("~8"));
+        assert_eq!(Some("8.10.2"), best("^8.4"));
+        assert_eq!(Some("8.6"), best(">=8.5, <8.7"));
+        assert_eq!(Some("8.5.1"), best("8.5.*"));
+        assert_eq!(Some("8.5"), best("=8.5.0"));
+        assert_eq!(Some("7.6.4"), best("<8"));
+        assert_eq!(Some("8.6-rc-1"), best(">=8.6-rc-1, <8.6"));
+        assert_eq!(None, best(">8.10.2"));
+        assert!(VersionConstraint::is_constraint(">=17, <22"));
+        assert!(!VersionConstraint::is_constraint("3.9.6"));
+        assert!(VersionConstraint::parse(">=").is_err());
+        assert!(VersionConstraint::parse("~latest").is_err());
+        let java = VersionConstraint::parse(">=17, <22").unwrap();
+        assert!(java.matches("21.0.2") && java.matches("17") && !java.matches("22") && !java.matches("11.0.22"));
+        let candidates = vec!["11".to_string(), "17".to_string()];
+        assert_eq!("No JDK version satisfies '>=21'; considered: 17, 11", java_error(&candidates));
+    }
+
+    fn java_error(candidates: &[String]) -> String {
+        VersionConstraint::parse(">=21").unwrap().unsatisfied("JDK", candidates).to_string()
+    }
+}